@@ -0,0 +1,324 @@
+//! Parses the `SignalCollection` type of the Telemetry API schema and generates the
+//! `Signal` catalog from it. Shared by the `generate_signals` example and the
+//! `signal_catalog` test.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+pub const SCHEMA: &str = "schema/telemetry.graphqls";
+pub const CATALOG: &str = "src/graphql/telemetry/catalog.rs";
+const LOCATION_PRIVILEGE: &str = "VEHICLE_ALL_TIME_LOCATION";
+
+pub struct SignalDef {
+    pub name: String,
+    pub variant: String,
+    pub description: String,
+    pub string: bool,
+    pub unit: Option<String>,
+    pub vss_path: String,
+    pub location: bool,
+}
+
+/// Reads the `SignalFloat` and `SignalString` fields of every `type SignalCollection` and
+/// `extend type SignalCollection` block, with the `Unit:`, `VSS:` and `Required Privileges:`
+/// lines of their descriptions. Descriptions may be block strings spanning several lines,
+/// one-line block strings or plain strings; a location privilege in the field's directives
+/// counts the same as one in its description.
+pub fn parse_schema(schema: &str) -> Result<Vec<SignalDef>, String> {
+    let mut signals: Vec<SignalDef> = Vec::new();
+    let mut found = false;
+    let mut in_collection = false;
+    let mut in_doc = false;
+    let mut doc: Vec<String> = Vec::new();
+    for line in schema.lines().map(str::trim) {
+        if !in_collection {
+            let declaration = line.strip_prefix("extend ").unwrap_or(line);
+            if let Some(rest) = declaration.strip_prefix("type SignalCollection") {
+                if rest.is_empty() || rest.starts_with([' ', '{', '@']) {
+                    in_collection = true;
+                    found = true;
+                    doc.clear();
+                }
+            }
+            continue;
+        }
+
+        if in_doc {
+            match line.split_once("\"\"\"") {
+                Some((text, _)) => {
+                    doc.push(text.trim().to_string());
+                    in_doc = false;
+                }
+                None => doc.push(line.to_string()),
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("\"\"\"") {
+            doc.clear();
+            match rest.split_once("\"\"\"") {
+                Some((text, _)) => doc.push(text.trim().to_string()),
+                None => {
+                    doc.push(rest.trim().to_string());
+                    in_doc = true;
+                }
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('"') {
+            doc = vec![rest
+                .rsplit_once('"')
+                .map_or(rest, |(text, _)| text)
+                .to_string()];
+            continue;
+        }
+        if line.starts_with('}') {
+            in_collection = false;
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let field_doc = std::mem::take(&mut doc);
+        let Some((name, field_type, directives)) = split_field(line) else {
+            continue;
+        };
+        let string = match field_type {
+            "SignalFloat" => false,
+            "SignalString" => true,
+            _ => continue,
+        };
+
+        let mut description = Vec::new();
+        let mut unit = None;
+        let mut vss_path = None;
+        let mut location = directives.contains(LOCATION_PRIVILEGE);
+        for doc_line in field_doc.iter().map(String::as_str) {
+            if let Some(value) = doc_line.strip_prefix("Unit:") {
+                unit = Some(value.trim().trim_matches(['\'', '"']).to_string());
+            } else if let Some(value) = doc_line.strip_prefix("VSS:") {
+                vss_path = Some(value.trim().to_string());
+            } else if let Some(value) = doc_line.strip_prefix("Required Privileges:") {
+                location |= value.contains(LOCATION_PRIVILEGE);
+            } else if !doc_line.is_empty() {
+                description.push(doc_line);
+            }
+        }
+
+        if signals.iter().any(|signal| signal.name == name) {
+            return Err(format!("signal {} is declared more than once", name));
+        }
+        signals.push(SignalDef {
+            variant: variant_name(name),
+            vss_path: vss_path.ok_or(format!("signal {} has no VSS path", name))?,
+            name: name.to_string(),
+            description: description.join(" "),
+            string,
+            unit,
+            location,
+        });
+    }
+
+    if !found {
+        return Err("no `type SignalCollection` found".to_string());
+    }
+    if signals.is_empty() {
+        return Err("`type SignalCollection` has no signals".to_string());
+    }
+    signals.sort_by(|a, b| a.variant.cmp(&b.variant));
+    Ok(signals)
+}
+
+/// Splits a field definition such as `speed: SignalFloat! @requiresAllOfPrivileges(...)` into
+/// its name, its named type without `!` or list brackets, and the rest of the line. Field
+/// arguments, as in `speed(filter: Filter): SignalFloat`, are skipped.
+fn split_field(line: &str) -> Option<(&str, &str, &str)> {
+    let name_end = line.find([':', '(', ' '])?;
+    let name = &line[..name_end];
+    let mut rest = &line[name_end..];
+    if rest.trim_start().starts_with('(') {
+        rest = &rest[rest.find(')')? + 1..];
+    }
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let type_end = rest.find([' ', '@']).unwrap_or(rest.len());
+    let field_type = rest[..type_end].trim_matches(['[', ']', '!']);
+    Some((name, field_type, &rest[type_end..]))
+}
+
+/// `powertrainCombustionEngineECT` -> `PowertrainCombustionEngineEct`, `dimoAftermarketWPAState`
+/// -> `DimoAftermarketWpaState`.
+fn variant_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut variant = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        let previous_upper = i > 0 && chars[i - 1].is_ascii_uppercase();
+        let next_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+        if i == 0 {
+            variant.push(c.to_ascii_uppercase());
+        } else if c.is_ascii_uppercase() && previous_upper && !next_lower {
+            variant.push(c.to_ascii_lowercase());
+        } else {
+            variant.push(*c);
+        }
+    }
+    variant
+}
+
+pub fn generate(signals: &[SignalDef]) -> String {
+    let mut out = String::new();
+    out.push_str("// Code generated by `cargo run --example generate_signals`. DO NOT EDIT.\n");
+    out.push_str(&format!("// Source: {}\n\n", SCHEMA));
+    out.push_str("use super::signals::SignalType;\n\n");
+
+    out.push_str(
+        "/// A vehicle signal that can be queried from the Telemetry API. New signals are added\n",
+    );
+    out.push_str("/// as the API gains them, so matches on it need a wildcard arm.\n///\n");
+    out.push_str(
+        "/// See <https://docs.dimo.org/developer-platform/api-references/telemetry-api>.\n",
+    );
+    out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]\n");
+    out.push_str("#[non_exhaustive]\n");
+    out.push_str("pub enum Signal {\n");
+    for signal in signals {
+        for line in wrap(&signal.description, 92) {
+            out.push_str(&format!("/// {}\n", line));
+        }
+        out.push_str(&format!("{},\n", signal.variant));
+    }
+    out.push_str("}\n\nimpl Signal {\n");
+
+    out.push_str(&format!("pub const ALL: [Signal; {}] = [\n", signals.len()));
+    for signal in signals {
+        out.push_str(&format!("Signal::{},\n", signal.variant));
+    }
+    out.push_str("];\n\n");
+
+    out.push_str("/// The field name in the Telemetry API schema, e.g. `powertrainRange`.\n");
+    out.push_str("pub fn name(self) -> &'static str {\nmatch self {\n");
+    for signal in signals {
+        out.push_str(&format!(
+            "Signal::{} => \"{}\",\n",
+            signal.variant, signal.name
+        ));
+    }
+    out.push_str("}\n}\n\n");
+
+    out.push_str("pub fn value_type(self) -> SignalType {\n");
+    let strings: Vec<String> = signals
+        .iter()
+        .filter(|signal| signal.string)
+        .map(|signal| format!("Signal::{}", signal.variant))
+        .collect();
+    if strings.is_empty() {
+        out.push_str("SignalType::Float\n}\n\n");
+    } else {
+        out.push_str(&format!(
+            "match self {{\n{} => SignalType::String,\n_ => SignalType::Float,\n}}\n}}\n\n",
+            strings.join(" | ")
+        ));
+    }
+
+    out.push_str("/// The unit of the signal's values, e.g. `km/h`, if it has one.\n");
+    out.push_str("pub fn unit(self) -> Option<&'static str> {\nmatch self {\n");
+    for signal in signals {
+        match signal.unit {
+            Some(ref unit) => out.push_str(&format!(
+                "Signal::{} => Some(\"{}\"),\n",
+                signal.variant, unit
+            )),
+            None => out.push_str(&format!("Signal::{} => None,\n", signal.variant)),
+        }
+    }
+    out.push_str("}\n}\n\n");
+
+    out.push_str(
+        "/// The path of the signal in the Vehicle Signal Specification, e.g. `Vehicle.Speed`.\n",
+    );
+    out.push_str("pub fn vss_path(self) -> &'static str {\nmatch self {\n");
+    for signal in signals {
+        out.push_str(&format!(
+            "Signal::{} => \"{}\",\n",
+            signal.variant, signal.vss_path
+        ));
+    }
+    out.push_str("}\n}\n\n");
+
+    out.push_str("/// Whether reading the signal needs [`crate::Privilege::AllTimeLocation`].\n");
+    out.push_str("pub fn is_location(self) -> bool {\n");
+    let locations: Vec<String> = signals
+        .iter()
+        .filter(|signal| signal.location)
+        .map(|signal| format!("Signal::{}", signal.variant))
+        .collect();
+    if locations.is_empty() {
+        out.push_str("false\n}\n");
+    } else {
+        out.push_str(&format!("matches!(self, {})\n}}\n", locations.join(" | ")));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Splits `text` into lines of at most `width` characters, breaking at spaces.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Formats generated code with `rustfmt`, which must be on the `PATH`.
+pub fn rustfmt(code: &str) -> Result<String, String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2021", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run rustfmt: {}", e))?;
+    child
+        .stdin
+        .take()
+        .expect("rustfmt stdin is piped")
+        .write_all(code.as_bytes())
+        .map_err(|e| format!("cannot write to rustfmt: {}", e))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("rustfmt failed: {}", e))?;
+    if !output.status.success() {
+        return Err("rustfmt could not format the generated catalog".to_string());
+    }
+    String::from_utf8(output.stdout).map_err(|_| "rustfmt output is not UTF-8".to_string())
+}
+
+/// The lines only in `current` prefixed with `-`, then the lines only in `generated`
+/// prefixed with `+`.
+pub fn diff(current: &str, generated: &str) -> String {
+    let current_lines: Vec<&str> = current.lines().collect();
+    let generated_lines: Vec<&str> = generated.lines().collect();
+    let mut out = String::new();
+    for line in current_lines
+        .iter()
+        .filter(|line| !generated_lines.contains(line))
+    {
+        out.push_str(&format!("-{}\n", line));
+    }
+    for line in generated_lines
+        .iter()
+        .filter(|line| !current_lines.contains(line))
+    {
+        out.push_str(&format!("+{}\n", line));
+    }
+    out
+}
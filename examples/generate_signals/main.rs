@@ -0,0 +1,51 @@
+//! Generates the `Signal` catalog in `src/graphql/telemetry/catalog.rs` from the vendored
+//! Telemetry API schema in `schema/telemetry.graphqls`.
+//!
+//! ```text
+//! cargo run --example generate_signals            # rewrite the catalog
+//! cargo run --example generate_signals -- --check # fail if the catalog is out of date
+//! ```
+//!
+//! The generated code is formatted with `rustfmt`, which must be on the `PATH`. The
+//! `signal_catalog` test runs the same check as `--check`.
+
+mod catalog;
+
+use catalog::{CATALOG, SCHEMA};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let check = env::args().any(|arg| arg == "--check");
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let schema = fs::read_to_string(root.join(SCHEMA))
+        .unwrap_or_else(|e| fail(&format!("cannot read {}: {}", SCHEMA, e)));
+    let signals =
+        catalog::parse_schema(&schema).unwrap_or_else(|e| fail(&format!("{}: {}", SCHEMA, e)));
+    let catalog = catalog::rustfmt(&catalog::generate(&signals)).unwrap_or_else(|e| fail(&e));
+
+    let path = root.join(CATALOG);
+    if !check {
+        fs::write(&path, catalog)
+            .unwrap_or_else(|e| fail(&format!("cannot write {}: {}", CATALOG, e)));
+        println!("wrote {} signals to {}", signals.len(), CATALOG);
+        return;
+    }
+
+    let current = fs::read_to_string(&path).unwrap_or_default();
+    if current == catalog {
+        println!("{} is up to date with {}", CATALOG, SCHEMA);
+        return;
+    }
+    eprintln!("{} is out of date with {}:", CATALOG, SCHEMA);
+    eprint!("{}", catalog::diff(&current, &catalog));
+    fail("run `cargo run --example generate_signals` to regenerate it");
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1)
+}
@@ -0,0 +1,346 @@
+use crate::chain::Chain;
+use crate::environments::dimo_environment::{
+    self, validate_url, Constants, Routes, PRODUCTION_CONSTANTS,
+};
+use crate::graphql::{Identity, Telemetry};
+use crate::rest::{
+    attestation::AttestationClient,
+    auth::{AuthClient, TokenCache, TokenManager},
+    devicedefinitions::DeviceDefinitions,
+    devices::Devices,
+    tokenexchange::{TokenExchange, VehicleTokenStore},
+    trips::Trips,
+    valuations::Valuations,
+};
+use crate::signer::{LocalSigner, Signer};
+use crate::utils::credentials::{
+    Credentials, CredentialsChain, CredentialsError, CredentialsProvider,
+};
+use crate::utils::request::{ClientConfig, HttpClient};
+use crate::utils::retry::RetryPolicy;
+use crate::utils::transport::HttpTransport;
+use crate::utils::DimoError;
+use crate::{Environment, DIMO};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Builds a [`DIMO`] client. Every setting is optional and checked by [`DimoBuilder::build`].
+///
+/// ```ignore
+/// let dimo = DIMO::builder()
+///     .environment(Environment::Dev)
+///     .credentials(Credentials::new("0x...", "https://example.com/callback"))
+///     .signer(LocalSigner::from_hex("0x...")?)
+///     .read_timeout(Duration::from_secs(10))
+///     .retry_policy(RetryPolicy::none())
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct DimoBuilder {
+    environment: Option<Environment>,
+    routes: Option<Routes>,
+    constants: Option<Constants>,
+    rpc_provider: Option<String>,
+    credentials: Option<Arc<dyn CredentialsProvider>>,
+    signer: Option<Arc<dyn Signer>>,
+    config: Option<ClientConfig>,
+    client: Option<HttpClient>,
+    retry: Option<RetryPolicy>,
+    token_cache: Option<Arc<dyn TokenCache>>,
+    /// Leave the credentials to the first challenge instead of loading them in `build()`.
+    lazy_credentials: bool,
+}
+
+impl DimoBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The DIMO environment to talk to. Defaults to [`Environment::Production`].
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Sends requests to `routes` instead of the environment's API hosts. The environment's
+    /// contract addresses are kept.
+    pub fn routes(mut self, routes: Routes) -> Self {
+        self.routes = Some(routes);
+        self
+    }
+
+    /// Uses the contracts and JSON-RPC endpoint in `constants` instead of the environment's.
+    /// [`Environment::Custom`] otherwise uses [`PRODUCTION_CONSTANTS`].
+    pub fn constants(mut self, constants: Constants) -> Self {
+        self.constants = Some(constants);
+        self
+    }
+
+    /// Sends [`DIMO::chain`] JSON-RPC calls to `url` instead of the environment's
+    /// `rpc_provider`.
+    pub fn rpc_provider(mut self, url: &str) -> Self {
+        self.rpc_provider = Some(url.to_string());
+        self
+    }
+
+    /// Reads the credentials from `provider` instead of [`CredentialsChain::default_chain`].
+    /// Either way they are loaded and checked at build time.
+    pub fn credentials<P: CredentialsProvider + 'static>(mut self, provider: P) -> Self {
+        self.credentials = Some(Arc::new(provider));
+        self
+    }
+
+    /// Signs the web3 challenge with `signer` instead of the credentials' private key.
+    pub fn signer<S: Signer + 'static>(mut self, signer: S) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Builds the HTTP client from `config`. Cannot be combined with
+    /// [`DimoBuilder::http_client`] or [`DimoBuilder::transport`].
+    pub fn config(mut self, config: ClientConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config
+            .get_or_insert_with(Default::default)
+            .connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config
+            .get_or_insert_with(Default::default)
+            .read_timeout = Some(timeout);
+        self
+    }
+
+    /// Shares a preconfigured [`HttpClient`] between the sub-clients.
+    pub fn http_client(mut self, client: HttpClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sends every request through `transport`, e.g. a [`crate::MockTransport`] in tests.
+    pub fn transport(self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.http_client(HttpClient::with_transport(transport))
+    }
+
+    /// Overrides the retry policy of the configured or default client.
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Keeps the developer JWT in `cache` instead of in memory.
+    pub fn token_cache<C: TokenCache + 'static>(mut self, cache: C) -> Self {
+        self.token_cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Loads the credentials on the first challenge rather than in [`DimoBuilder::build`],
+    /// as the infallible [`DIMO`] constructors do.
+    pub(crate) fn lazy_credentials(mut self) -> Self {
+        self.lazy_credentials = true;
+        self
+    }
+
+    /// Checks every setting and builds the client.
+    ///
+    /// The credentials are loaded here, and without a signer their private key must parse.
+    /// When no credentials provider was set and the default chain finds no credentials at
+    /// all, the client is still built, for endpoints that need no developer JWT.
+    pub fn build(self) -> Result<DIMO, DimoError> {
+        let preset = match self.environment.unwrap_or(Environment::Production) {
+            Environment::Production => dimo_environment::production(),
+            Environment::Dev => dimo_environment::dev(),
+            Environment::Custom(routes) => dimo_environment::Environment {
+                routes,
+                constants: PRODUCTION_CONSTANTS,
+            },
+        };
+        let routes = self.routes.unwrap_or(preset.routes);
+        routes.validate()?;
+        let constants = self.constants.unwrap_or(preset.constants);
+        let rpc_provider = self
+            .rpc_provider
+            .unwrap_or_else(|| constants.rpc_provider.to_string());
+        validate_url("rpc provider", &rpc_provider)?;
+
+        if let Some(ref retry) = self.retry {
+            retry.validate()?;
+        }
+        let client = match (self.config, self.client) {
+            (Some(_), Some(_)) => {
+                return Err(DimoError::Config(
+                    "client config and timeouts do not apply to a prebuilt HTTP client".to_string(),
+                ))
+            }
+            (config, None) => {
+                let mut config = config.unwrap_or_default();
+                if let Some(retry) = self.retry {
+                    config.retry = retry;
+                }
+                HttpClient::new(&config)?
+            }
+            (None, Some(client)) => match self.retry {
+                Some(retry) => client.with_retry_policy(retry),
+                None => client,
+            },
+        };
+
+        // The default chain may find nothing, which is fine for a client that only calls
+        // public endpoints; the first challenge then fails with the chain's error.
+        let credentials: Arc<dyn CredentialsProvider> = match self.credentials {
+            Some(provider) if self.lazy_credentials => provider,
+            None if self.lazy_credentials => Arc::new(CredentialsChain::default_chain()),
+            Some(provider) => Arc::new(checked(provider.credentials()?, &self.signer)?),
+            None => {
+                let chain = CredentialsChain::default_chain();
+                match chain.credentials() {
+                    Ok(credentials) => Arc::new(checked(credentials, &self.signer)?),
+                    Err(CredentialsError::MissingCredentials) => Arc::new(chain),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
+
+        let limits = client.rate_limits().clone();
+
+        let auth = AuthClient::new(routes.auth.to_string(), client.limited(limits.auth));
+        let identity = Identity::new(&routes.identity, client.limited(limits.identity));
+        let mut tokens = TokenManager::new(auth.clone())
+            .with_credentials(credentials)
+            .with_identity(identity.clone());
+        if let Some(signer) = self.signer {
+            tokens = tokens.with_signer(signer);
+        }
+        if let Some(cache) = self.token_cache {
+            tokens = tokens.with_cache(cache);
+        }
+        let tokens = Arc::new(tokens);
+        let client = client.with_token_manager(tokens.clone());
+
+        let tokenexchange = TokenExchange::new(
+            &routes.token_exchange,
+            constants.nft_address,
+            client.limited(limits.token_exchange),
+        );
+        let vehicle_tokens = Arc::new(VehicleTokenStore::new(tokenexchange.clone()));
+        let client = client.with_vehicle_token_store(vehicle_tokens.clone());
+
+        Ok(DIMO {
+            attestation: AttestationClient::new(
+                routes.attestation.to_string(),
+                client.limited(limits.attestation),
+            ),
+            auth,
+            chain: Chain::new(
+                &rpc_provider,
+                constants.vehicle_address,
+                constants.dlx_address,
+                client.clone(),
+            ),
+            devicedefinitions: DeviceDefinitions::new(
+                &routes.device_definitions,
+                client.limited(limits.device_definitions),
+            ),
+            devices: Devices::new(&routes.devices, client.limited(limits.devices)),
+            tokenexchange,
+            trips: Trips::new(&routes.trips, client.limited(limits.trips)),
+            valuations: Valuations::new(&routes.valuations, client.limited(limits.valuations)),
+            identity,
+            telemetry: Telemetry::new(&routes.telemetry, client.limited(limits.telemetry)),
+            tokens,
+            vehicle_tokens,
+        })
+    }
+}
+
+/// Checks that `credentials` can sign the challenge when no signer is configured.
+fn checked(
+    credentials: Credentials,
+    signer: &Option<Arc<dyn Signer>>,
+) -> Result<Credentials, DimoError> {
+    if signer.is_none() {
+        let private_key = credentials
+            .private_key
+            .as_deref()
+            .ok_or(CredentialsError::MissingFields(vec!["api_key"]))?;
+        LocalSigner::from_hex(private_key)?;
+    }
+    Ok(credentials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{signer, CLIENT_ID, DOMAIN, PRIVATE_KEY};
+
+    fn build(credentials: Credentials, with_signer: bool) -> Result<DIMO, DimoError> {
+        let builder = DimoBuilder::new().credentials(credentials);
+        if with_signer {
+            builder.signer(signer()).build()
+        } else {
+            builder.build()
+        }
+    }
+
+    #[test]
+    fn checks_the_private_key_only_without_a_signer() {
+        let credentials = Credentials::new(CLIENT_ID, DOMAIN);
+        assert!(matches!(
+            build(credentials.clone(), false),
+            Err(DimoError::Credentials(CredentialsError::MissingFields(fields)))
+                if fields == ["api_key"]
+        ));
+        assert!(build(credentials.clone().with_private_key("0x12"), false).is_err());
+        build(credentials.clone().with_private_key(PRIVATE_KEY), false).unwrap();
+        build(credentials, true).unwrap();
+    }
+
+    #[test]
+    fn checks_the_rpc_provider_of_custom_constants() {
+        let custom = || {
+            DimoBuilder::new()
+                .environment(Environment::Custom(Routes::dev()))
+                .credentials(Credentials::new(CLIENT_ID, DOMAIN))
+                .signer(signer())
+        };
+        let constants = Constants {
+            rpc_provider: "not a url",
+            ..dimo_environment::DEV_CONSTANTS
+        };
+
+        let Err(err) = custom().constants(constants).build() else {
+            panic!("built with an invalid rpc provider");
+        };
+        assert!(err.to_string().contains("rpc provider"), "{}", err);
+        custom().build().unwrap();
+    }
+
+    #[test]
+    fn fails_on_incomplete_credentials() {
+        assert!(matches!(
+            build(Credentials::new(CLIENT_ID, ""), true),
+            Err(DimoError::Credentials(CredentialsError::MissingFields(_)))
+        ));
+    }
+
+    #[test]
+    fn constructors_leave_the_credentials_to_the_first_request() {
+        // Incomplete credentials only fail the first challenge.
+        DIMO::with_credentials(Environment::Dev, Credentials::new(CLIENT_ID, ""));
+        DIMO::new(Environment::Production);
+
+        let mut routes = Routes::dev();
+        routes.trips = "trips-api".into();
+        let Err(err) = DIMO::custom(routes) else {
+            panic!("built with an invalid route");
+        };
+        assert!(err.to_string().contains("route trips"), "{}", err);
+        DIMO::custom(Routes::dev()).unwrap();
+    }
+}
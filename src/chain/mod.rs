@@ -0,0 +1,331 @@
+//! Read-only access to the DIMO contracts through an Ethereum JSON-RPC endpoint, so that
+//! vehicle ownership can be checked without the Identity API.
+
+use crate::signer::{checksum_address, to_checksum_address};
+use crate::utils::request::{HttpClient, RequestParams};
+use crate::utils::DimoError;
+use reqwest::Method;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+/// A minimal JSON-RPC client for the chain holding the DIMO contracts.
+///
+/// [`crate::DIMO`] points it at the environment's `rpc_provider`; use [`Chain::new`] with
+/// any other endpoint, e.g. a local node or stub.
+#[derive(Clone)]
+pub struct Chain {
+    rpc_url: String,
+    vehicle_address: String,
+    dlx_address: String,
+    client: HttpClient,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceipt {
+    pub transaction_hash: String,
+    pub block_hash: String,
+    #[serde(deserialize_with = "quantity")]
+    pub block_number: u64,
+    pub from: String,
+    /// `None` for contract creations.
+    pub to: Option<String>,
+    pub contract_address: Option<String>,
+    #[serde(deserialize_with = "quantity")]
+    pub gas_used: u64,
+    /// `0x1` on success and `0x0` on revert; absent before the Byzantium fork.
+    pub status: Option<String>,
+    pub logs: Vec<Log>,
+}
+
+impl TransactionReceipt {
+    /// Whether the transaction succeeded, when the receipt says so.
+    pub fn succeeded(&self) -> Option<bool> {
+        self.status.as_deref().map(|status| status == "0x1")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+}
+
+impl Chain {
+    pub fn new(
+        rpc_url: &str,
+        vehicle_address: &str,
+        dlx_address: &str,
+        client: HttpClient,
+    ) -> Self {
+        Self {
+            rpc_url: rpc_url.to_string(),
+            vehicle_address: vehicle_address.to_string(),
+            dlx_address: dlx_address.to_string(),
+            client,
+        }
+    }
+
+    pub async fn chain_id(&self) -> Result<u64, DimoError> {
+        let chain_id = self.call("eth_chainId", Vec::new()).await?;
+        parse_quantity(&chain_id).ok_or_else(|| unexpected("eth_chainId", &chain_id))
+    }
+
+    /// Runs a read-only call of `to` with ABI encoded `data` against the latest block.
+    pub async fn eth_call(&self, to: &str, data: &[u8]) -> Result<Vec<u8>, DimoError> {
+        let mut call = serde_json::Map::new();
+        call.insert("to".to_string(), Value::from(to));
+        call.insert(
+            "data".to_string(),
+            Value::from(format!("0x{}", hex::encode(data))),
+        );
+
+        let result = self
+            .call("eth_call", vec![Value::Object(call), Value::from("latest")])
+            .await?;
+        result
+            .as_str()
+            .and_then(|result| hex::decode(result.strip_prefix("0x").unwrap_or(result)).ok())
+            .ok_or_else(|| unexpected("eth_call", &result))
+    }
+
+    /// The receipt of `transaction_hash`, or `None` while it is pending or unknown.
+    pub async fn transaction_receipt(
+        &self,
+        transaction_hash: &str,
+    ) -> Result<Option<TransactionReceipt>, DimoError> {
+        let receipt = self
+            .call(
+                "eth_getTransactionReceipt",
+                vec![Value::from(transaction_hash)],
+            )
+            .await?;
+        Ok(serde_json::from_value(receipt)?)
+    }
+
+    /// The EIP-55 address owning the vehicle NFT `token_id`. Fails with
+    /// [`DimoError::JsonRpc`] when the token does not exist.
+    pub async fn owner_of(&self, token_id: u64) -> Result<String, DimoError> {
+        let mut data = selector("ownerOf(uint256)").to_vec();
+        data.extend_from_slice(&encode_uint(token_id));
+
+        let result = self.eth_call(&self.vehicle_address, &data).await?;
+        decode_address(&result)
+            .ok_or_else(|| unexpected("eth_call", &Value::from(hex::encode(&result))))
+    }
+
+    /// The DLx balance of `address`, in the token's smallest unit. Balances that do not
+    /// fit in a `u128` are reported as an unexpected result.
+    pub async fn dlx_balance(&self, address: &str) -> Result<u128, DimoError> {
+        if self.dlx_address.is_empty() {
+            return Err(DimoError::Config(
+                "no DLx contract is configured for this environment".to_string(),
+            ));
+        }
+        let mut data = selector("balanceOf(address)").to_vec();
+        data.extend_from_slice(&encode_address(address)?);
+
+        let result = self.eth_call(&self.dlx_address, &data).await?;
+        decode_uint(&result)
+            .ok_or_else(|| unexpected("eth_call", &Value::from(hex::encode(&result))))
+    }
+
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, DimoError> {
+        let mut body: HashMap<String, Value> = HashMap::new();
+        body.insert("jsonrpc".to_string(), Value::from("2.0"));
+        body.insert("id".to_string(), Value::from(1));
+        body.insert("method".to_string(), Value::from(method));
+        body.insert("params".to_string(), Value::Array(params));
+
+        let request_params = RequestParams {
+            method: Method::POST,
+            idempotent: true,
+            base_url: self.rpc_url.clone(),
+            path: String::new(),
+            query_params: None,
+            body: Some(body),
+            headers: None,
+        };
+
+        let mut response = self.client.make_request(request_params).await?;
+        if let Some(error) = response.get("error") {
+            return Err(DimoError::JsonRpc {
+                method: method.to_string(),
+                code: error.get("code").and_then(Value::as_i64),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+            });
+        }
+        Ok(response["result"].take())
+    }
+}
+
+/// The first 4 bytes of the hash of a function signature such as `ownerOf(uint256)`.
+fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn encode_uint(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+fn encode_address(address: &str) -> Result<[u8; 32], DimoError> {
+    let bytes = hex::decode(&to_checksum_address(address)?[2..])?;
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(&bytes);
+    Ok(word)
+}
+
+fn decode_address(word: &[u8]) -> Option<String> {
+    let bytes: [u8; 20] = word.get(12..32)?.try_into().ok()?;
+    Some(checksum_address(&bytes))
+}
+
+fn decode_uint(word: &[u8]) -> Option<u128> {
+    if word.len() != 32 || word[..16].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(u128::from_be_bytes(word[16..].try_into().ok()?))
+}
+
+fn parse_quantity(value: &Value) -> Option<u64> {
+    let value = value.as_str()?;
+    u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()
+}
+
+/// Deserializes a hex encoded JSON-RPC quantity such as `"0x1b4"`.
+fn quantity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    parse_quantity(&value)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid quantity {}", value)))
+}
+
+fn unexpected(method: &str, result: &Value) -> DimoError {
+    DimoError::JsonRpc {
+        method: method.to_string(),
+        code: None,
+        message: format!("unexpected result {}", result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{body, ok, SIGNER_ADDRESS};
+    use crate::utils::transport::{HttpResponse, MockTransport};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const VEHICLE: &str = "0xbA5738a18d83D41847dfFbDC6101d37C69c9B0cF";
+    const DLX: &str = "0x9A9D2E717bB005B240094ba761Ff074d392C7C85";
+
+    fn chain(transport: &Arc<MockTransport>) -> Chain {
+        Chain::new(
+            "http://localhost:8545",
+            VEHICLE,
+            DLX,
+            HttpClient::with_transport(transport.clone()),
+        )
+    }
+
+    fn result(result: Value) -> HttpResponse {
+        ok(json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+    }
+
+    #[tokio::test]
+    async fn reads_the_owner_of_a_vehicle() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(result(json!(format!(
+            "0x000000000000000000000000{}",
+            SIGNER_ADDRESS[2..].to_lowercase()
+        ))));
+
+        let owner = chain(&transport).owner_of(123).await.unwrap();
+        assert_eq!(owner, SIGNER_ADDRESS);
+
+        let sent = body(&transport.requests()[0]);
+        assert_eq!(sent["method"], "eth_call");
+        assert_eq!(sent["params"][0]["to"], VEHICLE);
+        assert_eq!(sent["params"][0]["data"], format!("0x6352211e{:064x}", 123));
+        assert_eq!(sent["params"][1], "latest");
+    }
+
+    #[tokio::test]
+    async fn reads_a_dlx_balance() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(result(json!(format!(
+            "0x{:064x}",
+            5_000_000_000_000_000_000u128
+        ))));
+
+        let balance = chain(&transport).dlx_balance(SIGNER_ADDRESS).await.unwrap();
+        assert_eq!(balance, 5_000_000_000_000_000_000);
+
+        let sent = body(&transport.requests()[0]);
+        assert_eq!(sent["params"][0]["to"], DLX);
+        assert_eq!(
+            sent["params"][0]["data"],
+            format!(
+                "0x70a08231000000000000000000000000{}",
+                SIGNER_ADDRESS[2..].to_lowercase()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn surfaces_json_rpc_errors() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(ok(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": 3, "message": "execution reverted: ERC721: invalid token ID" },
+        })));
+
+        let err = chain(&transport).owner_of(999).await.unwrap_err();
+        match err {
+            DimoError::JsonRpc {
+                method,
+                code,
+                message,
+            } => {
+                assert_eq!(method, "eth_call");
+                assert_eq!(code, Some(3));
+                assert!(message.contains("invalid token ID"));
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_none_for_an_unknown_receipt() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(result(Value::Null));
+        transport.push(result(json!({
+            "transactionHash": "0xabc",
+            "blockHash": "0xdef",
+            "blockNumber": "0x1b4",
+            "from": SIGNER_ADDRESS,
+            "to": VEHICLE,
+            "contractAddress": null,
+            "gasUsed": "0x5208",
+            "status": "0x1",
+            "logs": [],
+        })));
+        let chain = chain(&transport);
+
+        assert!(chain.transaction_receipt("0xabc").await.unwrap().is_none());
+        let receipt = chain.transaction_receipt("0xabc").await.unwrap().unwrap();
+        assert_eq!(receipt.block_number, 436);
+        assert_eq!(receipt.gas_used, 21_000);
+        assert_eq!(receipt.succeeded(), Some(true));
+    }
+}
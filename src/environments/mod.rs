@@ -1,20 +1,31 @@
 pub mod dimo_environment {
+    use crate::utils::DimoError;
+    use reqwest::Url;
+    use serde::Deserialize;
+    use std::borrow::Cow;
+    use std::env;
     use std::fmt::Debug;
+    use std::fs;
+    use std::path::Path;
 
-    #[derive(Debug)]
+    /// The base URL of each DIMO API.
+    #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+    #[serde(deny_unknown_fields)]
     pub struct Routes {
-        pub attestation: &'static str,
-        pub auth: &'static str,
-        pub identity: &'static str,
-        pub devices: &'static str,
-        pub device_definitions: &'static str,
-        pub events: &'static str,
-        pub telemetry: &'static str,
-        pub token_exchange: &'static str,
-        pub trips: &'static str,
-        pub valuations: &'static str,
+        pub attestation: Cow<'static, str>,
+        pub auth: Cow<'static, str>,
+        pub identity: Cow<'static, str>,
+        pub devices: Cow<'static, str>,
+        pub device_definitions: Cow<'static, str>,
+        pub events: Cow<'static, str>,
+        pub telemetry: Cow<'static, str>,
+        pub token_exchange: Cow<'static, str>,
+        pub trips: Cow<'static, str>,
+        pub valuations: Cow<'static, str>,
     }
 
+    /// The contracts of an environment and the JSON-RPC endpoint of the chain holding them.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct Constants {
         pub nft_address: &'static str,
         pub rpc_provider: &'static str,
@@ -22,50 +33,314 @@ pub mod dimo_environment {
         pub vehicle_address: &'static str,
     }
 
+    #[derive(Debug, Clone)]
     pub struct Environment {
         pub routes: Routes,
         pub constants: Constants,
     }
 
+    /// The DIMO contracts on Polygon.
+    pub const PRODUCTION_CONSTANTS: Constants = Constants {
+        nft_address: "0xbA5738a18d83D41847dfFbDC6101d37C69c9B0cF",
+        rpc_provider: "https://polygon-rpc.com",
+        dlx_address: "0x9A9D2E717bB005B240094ba761Ff074d392C7C85",
+        vehicle_address: "0xba5738a18d83d41847dffbdc6101d37c69c9b0cf",
+    };
+
+    /// The DIMO contracts on the Polygon Amoy testnet.
+    pub const DEV_CONSTANTS: Constants = Constants {
+        nft_address: "0x45fbCD3ef7361d156e8b16F5538AE36DEdf61Da8",
+        rpc_provider: "https://rpc-amoy.polygon.technology",
+        dlx_address: "",
+        vehicle_address: "0x45fbCD3ef7361d156e8b16F5538AE36DEdf61Da8",
+    };
+
+    #[deprecated(note = "use `production()`, or `Routes::production()` and `PRODUCTION_CONSTANTS`")]
     pub const PRODUCTION: Environment = Environment {
-        routes: Routes {
-            attestation: "https://attestation-api.dimo.zone",
-            auth: "https://auth.dimo.zone",
-            identity: "https://identity-api.dimo.zone/query",
-            devices: "https://devices-api.dimo.zone",
-            device_definitions: "https://device-definitions-api.dimo.zone",
-            events: "https://events-api.dimo.zone",
-            telemetry: "https://telemetry-api.dimo.zone/query",
-            token_exchange: "https://token-exchange-api.dimo.zone",
-            trips: "https://trips-api.dimo.zone",
-            valuations: "https://valuations-api.dimo.zone",
-        },
-        constants: Constants {
-            nft_address: "0xbA5738a18d83D41847dfFbDC6101d37C69c9B0cF",
-            rpc_provider: "https://eth.llamarpc.com",
-            dlx_address: "0x9A9D2E717bB005B240094ba761Ff074d392C7C85",
-            vehicle_address: "0xba5738a18d83d41847dffbdc6101d37c69c9b0cf",
-        },
+        routes: PRODUCTION_ROUTES,
+        constants: PRODUCTION_CONSTANTS,
     };
 
+    #[deprecated(note = "use `dev()`, or `Routes::dev()` and `DEV_CONSTANTS`")]
     pub const DEV: Environment = Environment {
-        routes: Routes {
-            attestation: "https://attestation-api.dev.dimo.zone",
-            auth: "https://auth.dev.dimo.zone",
-            identity: "https://identity-api.dev.dimo.zone/query",
-            devices: "https://devices-api.dev.dimo.zone",
-            device_definitions: "https://device-definitions-api.dev.dimo.zone",
-            events: "https://events-api.dev.dimo.zone",
-            telemetry: "https://telemetry-api.dev.dimo.zone/query",
-            token_exchange: "https://token-exchange-api.dev.dimo.zone",
-            trips: "https://trips-api.dev.dimo.zone",
-            valuations: "https://valuations-api.dev.dimo.zone",
-        },
-        constants: Constants {
-            nft_address: "0x45fbCD3ef7361d156e8b16F5538AE36DEdf61Da8",
-            rpc_provider: "https://eth.llamarpc.com",
-            dlx_address: "",
-            vehicle_address: "0x45fbCD3ef7361d156e8b16F5538AE36DEdf61Da8",
-        },
+        routes: DEV_ROUTES,
+        constants: DEV_CONSTANTS,
+    };
+
+    pub fn production() -> Environment {
+        Environment {
+            routes: Routes::production(),
+            constants: PRODUCTION_CONSTANTS,
+        }
+    }
+
+    pub fn dev() -> Environment {
+        Environment {
+            routes: Routes::dev(),
+            constants: DEV_CONSTANTS,
+        }
+    }
+
+    const PRODUCTION_ROUTES: Routes = Routes {
+        attestation: Cow::Borrowed("https://attestation-api.dimo.zone"),
+        auth: Cow::Borrowed("https://auth.dimo.zone"),
+        identity: Cow::Borrowed("https://identity-api.dimo.zone/query"),
+        devices: Cow::Borrowed("https://devices-api.dimo.zone"),
+        device_definitions: Cow::Borrowed("https://device-definitions-api.dimo.zone"),
+        events: Cow::Borrowed("https://events-api.dimo.zone"),
+        telemetry: Cow::Borrowed("https://telemetry-api.dimo.zone/query"),
+        token_exchange: Cow::Borrowed("https://token-exchange-api.dimo.zone"),
+        trips: Cow::Borrowed("https://trips-api.dimo.zone"),
+        valuations: Cow::Borrowed("https://valuations-api.dimo.zone"),
     };
+
+    const DEV_ROUTES: Routes = Routes {
+        attestation: Cow::Borrowed("https://attestation-api.dev.dimo.zone"),
+        auth: Cow::Borrowed("https://auth.dev.dimo.zone"),
+        identity: Cow::Borrowed("https://identity-api.dev.dimo.zone/query"),
+        devices: Cow::Borrowed("https://devices-api.dev.dimo.zone"),
+        device_definitions: Cow::Borrowed("https://device-definitions-api.dev.dimo.zone"),
+        events: Cow::Borrowed("https://events-api.dev.dimo.zone"),
+        telemetry: Cow::Borrowed("https://telemetry-api.dev.dimo.zone/query"),
+        token_exchange: Cow::Borrowed("https://token-exchange-api.dev.dimo.zone"),
+        trips: Cow::Borrowed("https://trips-api.dev.dimo.zone"),
+        valuations: Cow::Borrowed("https://valuations-api.dev.dimo.zone"),
+    };
+
+    /// The environment variable that overrides each route, see [`Routes::from_env`].
+    const ROUTE_VARS: [&str; 10] = [
+        "DIMO_ATTESTATION_URL",
+        "DIMO_AUTH_URL",
+        "DIMO_IDENTITY_URL",
+        "DIMO_DEVICES_URL",
+        "DIMO_DEVICE_DEFINITIONS_URL",
+        "DIMO_EVENTS_URL",
+        "DIMO_TELEMETRY_URL",
+        "DIMO_TOKEN_EXCHANGE_URL",
+        "DIMO_TRIPS_URL",
+        "DIMO_VALUATIONS_URL",
+    ];
+
+    impl Routes {
+        pub fn production() -> Self {
+            PRODUCTION_ROUTES
+        }
+
+        pub fn dev() -> Self {
+            DEV_ROUTES
+        }
+
+        /// Reads every route from a TOML file with one key per field, e.g.
+        /// `telemetry = "http://localhost:8080/query"`.
+        pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, DimoError> {
+            let path = path.as_ref();
+            let contents = fs::read_to_string(path).map_err(|e| {
+                DimoError::Config(format!("cannot read routes file {}: {}", path.display(), e))
+            })?;
+            Self::from_toml_str(&contents)
+        }
+
+        pub fn from_toml_str(contents: &str) -> Result<Self, DimoError> {
+            let routes: Self = toml::from_str(contents)
+                .map_err(|e| DimoError::Config(format!("invalid routes file: {}", e)))?;
+            routes.validate()?;
+            Ok(routes)
+        }
+
+        /// Reads every route from the `DIMO_<ROUTE>_URL` environment variables, e.g.
+        /// `DIMO_TELEMETRY_URL` or `DIMO_TOKEN_EXCHANGE_URL`. All of them must be set.
+        pub fn from_env() -> Result<Self, DimoError> {
+            let missing: Vec<&str> = ROUTE_VARS
+                .iter()
+                .copied()
+                .filter(|name| env::var(name).is_err())
+                .collect();
+            if !missing.is_empty() {
+                return Err(DimoError::Config(format!(
+                    "missing route variables: {}",
+                    missing.join(", ")
+                )));
+            }
+            Self::production().with_env_overrides()
+        }
+
+        /// Replaces the routes whose `DIMO_<ROUTE>_URL` environment variable is set, e.g. to
+        /// point only the Telemetry API of [`Routes::dev`] at a local mock.
+        pub fn with_env_overrides(mut self) -> Result<Self, DimoError> {
+            for (name, route) in ROUTE_VARS.iter().zip(self.fields_mut()) {
+                if let Ok(url) = env::var(name) {
+                    *route = Cow::Owned(url);
+                }
+            }
+            self.validate()?;
+            Ok(self)
+        }
+
+        /// Checks that every route is an absolute `http` or `https` URL.
+        pub fn validate(&self) -> Result<(), DimoError> {
+            for (name, route) in self.named() {
+                validate_url(&format!("route {}", name), route)?;
+            }
+            Ok(())
+        }
+
+        fn named(&self) -> [(&'static str, &str); 10] {
+            [
+                ("attestation", &self.attestation),
+                ("auth", &self.auth),
+                ("identity", &self.identity),
+                ("devices", &self.devices),
+                ("device_definitions", &self.device_definitions),
+                ("events", &self.events),
+                ("telemetry", &self.telemetry),
+                ("token_exchange", &self.token_exchange),
+                ("trips", &self.trips),
+                ("valuations", &self.valuations),
+            ]
+        }
+
+        /// The routes in the order of [`ROUTE_VARS`].
+        fn fields_mut(&mut self) -> [&mut Cow<'static, str>; 10] {
+            [
+                &mut self.attestation,
+                &mut self.auth,
+                &mut self.identity,
+                &mut self.devices,
+                &mut self.device_definitions,
+                &mut self.events,
+                &mut self.telemetry,
+                &mut self.token_exchange,
+                &mut self.trips,
+                &mut self.valuations,
+            ]
+        }
+    }
+
+    /// Checks that `url` is an absolute `http` or `https` URL, naming it `what` otherwise.
+    pub(crate) fn validate_url(what: &str, url: &str) -> Result<(), DimoError> {
+        let valid = Url::parse(url)
+            .map(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+            .unwrap_or(false);
+        if !valid {
+            return Err(DimoError::Config(format!(
+                "{} is not an http(s) URL: '{}'",
+                what, url
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::io::Write;
+
+        fn local_toml() -> String {
+            [
+                "attestation",
+                "auth",
+                "identity",
+                "devices",
+                "device_definitions",
+                "events",
+                "telemetry",
+                "token_exchange",
+                "trips",
+                "valuations",
+            ]
+            .iter()
+            .map(|key| format!("{} = \"http://localhost:8080/{}\"\n", key, key))
+            .collect()
+        }
+
+        #[test]
+        fn reads_routes_from_a_toml_file() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(local_toml().as_bytes()).unwrap();
+
+            let routes = Routes::from_toml_file(file.path()).unwrap();
+            assert_eq!(routes.telemetry, "http://localhost:8080/telemetry");
+            assert_eq!(
+                routes.token_exchange,
+                "http://localhost:8080/token_exchange"
+            );
+
+            let err = Routes::from_toml_file(file.path().with_extension("missing")).unwrap_err();
+            assert!(err.to_string().contains("cannot read routes file"));
+        }
+
+        #[test]
+        fn rejects_toml_with_missing_or_unknown_keys() {
+            let missing = local_toml().replace("trips = \"http://localhost:8080/trips\"\n", "");
+            let err = Routes::from_toml_str(&missing).unwrap_err();
+            assert!(err.to_string().contains("missing field `trips`"), "{}", err);
+
+            let unknown = format!("{}vehicles = \"http://localhost:8080\"\n", local_toml());
+            let err = Routes::from_toml_str(&unknown).unwrap_err();
+            assert!(
+                err.to_string().contains("unknown field `vehicles`"),
+                "{}",
+                err
+            );
+        }
+
+        #[test]
+        fn rejects_routes_that_are_not_http_urls() {
+            for url in [
+                "ftp://localhost:8080/auth",
+                "localhost:8080",
+                "not a url",
+                "",
+            ] {
+                let toml = local_toml().replace("http://localhost:8080/auth", url);
+                match Routes::from_toml_str(&toml) {
+                    Err(DimoError::Config(message)) => {
+                        assert!(
+                            message.contains("route auth is not an http(s) URL"),
+                            "{}",
+                            message
+                        )
+                    }
+                    result => panic!("accepted {:?}: {:?}", url, result),
+                }
+            }
+
+            let mut routes = Routes::dev();
+            routes.validate().unwrap();
+            routes.identity = Cow::Borrowed("file:///etc/passwd");
+            assert!(routes.validate().is_err());
+        }
+
+        /// The only test touching the route variables, so that tests running in parallel do
+        /// not see each other's values.
+        #[test]
+        fn overrides_only_the_routes_whose_variables_are_set() {
+            env::set_var("DIMO_TELEMETRY_URL", "http://localhost:8080/query");
+            env::set_var("DIMO_AUTH_URL", "http://localhost:8081");
+
+            let routes = Routes::dev().with_env_overrides();
+            let partial = Routes::from_env();
+            env::set_var("DIMO_AUTH_URL", "localhost:8081");
+            let invalid = Routes::dev().with_env_overrides();
+            env::remove_var("DIMO_TELEMETRY_URL");
+            env::remove_var("DIMO_AUTH_URL");
+
+            let routes = routes.unwrap();
+            assert_eq!(routes.telemetry, "http://localhost:8080/query");
+            assert_eq!(routes.auth, "http://localhost:8081");
+            assert_eq!(
+                Routes {
+                    telemetry: DEV_ROUTES.telemetry,
+                    auth: DEV_ROUTES.auth,
+                    ..routes
+                },
+                Routes::dev()
+            );
+
+            let err = partial.unwrap_err().to_string();
+            assert!(err.contains("DIMO_DEVICES_URL"), "{}", err);
+            assert!(!err.contains("DIMO_TELEMETRY_URL"), "{}", err);
+            assert!(invalid.is_err());
+        }
+    }
 }
@@ -1,36 +1,54 @@
-use crate::utils::request::{make_request, RequestParams};
+use super::{check_errors, GraphQLRequest, GraphQLResponse};
+use crate::utils::request::{to_body, HttpClient, RequestParams};
+use crate::utils::DimoError;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::error::Error;
 
+#[derive(Clone)]
 pub struct Identity {
     base_url: String,
+    client: HttpClient,
 }
 
 impl Identity {
-    pub fn new(base_url: &str) -> Self {
+    pub fn new(base_url: &str, client: HttpClient) -> Self {
         Self {
             base_url: base_url.to_string(),
+            client,
         }
     }
 
-    pub async fn query(&self, query: &str) -> Result<Value, Box<dyn Error>> {
-        let mut body = HashMap::new();
-        body.insert("query".to_string(), Value::String(query.to_string()));
+    pub async fn query(&self, query: &str) -> Result<Value, DimoError> {
+        let response = self.execute_raw(&GraphQLRequest::new(query)).await?;
+        check_errors(response)
+    }
 
+    /// Sends `request` and deserializes its `data` into `T`.
+    pub async fn execute<T: DeserializeOwned>(
+        &self,
+        request: &GraphQLRequest,
+    ) -> Result<GraphQLResponse<T>, DimoError> {
+        let response = self.execute_raw(request).await?;
+        GraphQLResponse::from_value(response)
+    }
+
+    /// Like [`Identity::execute`], but returns the whole response as JSON.
+    pub async fn execute_raw(&self, request: &GraphQLRequest) -> Result<Value, DimoError> {
         let params = RequestParams {
             method: reqwest::Method::POST,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path: "".to_string(),
             query_params: None,
-            body: Some(body),
+            body: Some(to_body(request)?),
             headers: None,
         };
 
-        make_request(params).await
+        self.client.make_request(params).await
     }
 
-    pub async fn count_dimo_vehicles(&self) -> Result<Value, Box<dyn Error>> {
+    pub async fn count_dimo_vehicles(&self) -> Result<Value, DimoError> {
         let query = "
         {
             vehicles (first: 10) {
@@ -45,35 +63,165 @@ impl Identity {
         &self,
         address: &str,
         limit: Option<i32>,
-    ) -> Result<Value, Box<dyn Error>> {
-        let limit = limit.unwrap_or(10);
-
-        let query = format!(
-            r#"
-            {{
-                vehicles(filterBy: {{owner: "{address}"}}, first: {limit}) {{
-                    nodes {{
-                        aftermarketDevice {{
+    ) -> Result<Value, DimoError> {
+        let query = r#"
+            query VehiclesByOwner($owner: Address!, $first: Int!) {
+                vehicles(filterBy: {owner: $owner}, first: $first) {
+                    nodes {
+                        aftermarketDevice {
                             tokenId
                             address
-                        }}
-                        syntheticDevice {{
+                        }
+                        syntheticDevice {
                             address
                             tokenId
-                        }}
-                        definition {{
+                        }
+                        definition {
                             make
                             model
                             year
-                        }}
-                    }}
-                }}
-            }}
-            "#,
-            address = address,
-            limit = limit
+                        }
+                    }
+                }
+            }
+        "#;
+        let request = GraphQLRequest::new(query)
+            .with_variable("owner", address)
+            .with_variable("first", limit.unwrap_or(10));
+
+        let response = self.execute_raw(&request).await?;
+        check_errors(response)
+    }
+
+    /// The addresses allowed to sign the web3 challenge for the developer license
+    /// `client_id`, following the pages of the `signers` connection.
+    pub async fn developer_license_signers(
+        &self,
+        client_id: &str,
+    ) -> Result<Vec<String>, DimoError> {
+        let query = r#"
+            query DeveloperLicenseSigners($clientId: Address!, $after: String) {
+                developerLicense(by: {clientId: $clientId}) {
+                    signers(first: 100, after: $after) {
+                        nodes {
+                            address
+                        }
+                        pageInfo {
+                            hasNextPage
+                            endCursor
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let mut signers = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let request = GraphQLRequest::new(query)
+                .with_variable("clientId", client_id)
+                .with_variable("after", after.take());
+            let data: DeveloperLicenseData = self.execute(&request).await?.into_data()?;
+            let Some(license) = data.developer_license else {
+                break;
+            };
+
+            let page = license.signers;
+            signers.extend(page.nodes.into_iter().map(|node| node.address));
+            match page.page_info {
+                Some(PageInfo {
+                    has_next_page: true,
+                    end_cursor: Some(cursor),
+                }) => after = Some(cursor),
+                _ => break,
+            }
+        }
+        Ok(signers)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeveloperLicenseData {
+    developer_license: Option<DeveloperLicense>,
+}
+
+#[derive(Deserialize)]
+struct DeveloperLicense {
+    signers: Connection<SignerNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    nodes: Vec<T>,
+    page_info: Option<PageInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SignerNode {
+    address: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{body, ok};
+    use crate::utils::transport::MockTransport;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn page(addresses: &[&str], end_cursor: Option<&str>) -> Value {
+        let nodes: Vec<Value> = addresses.iter().map(|a| json!({ "address": a })).collect();
+        json!({ "data": { "developerLicense": { "signers": {
+            "nodes": nodes,
+            "pageInfo": { "hasNextPage": end_cursor.is_some(), "endCursor": end_cursor },
+        } } } })
+    }
+
+    #[tokio::test]
+    async fn follows_every_page_of_signers() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(ok(page(&["0x01", "0x02"], Some("c1"))))
+            .push(ok(page(&["0x03"], Some("c2"))))
+            .push(ok(page(&["0x04"], None)));
+        let identity = Identity::new(
+            "https://identity-api.dimo.zone/query",
+            HttpClient::with_transport(transport.clone()),
+        );
+
+        let signers = identity.developer_license_signers("0xabc").await.unwrap();
+        assert_eq!(signers, ["0x01", "0x02", "0x03", "0x04"]);
+
+        let afters: Vec<Value> = transport
+            .requests()
+            .iter()
+            .map(|r| body(r)["variables"]["after"].clone())
+            .collect();
+        assert_eq!(afters, [Value::Null, json!("c1"), json!("c2")]);
+    }
+
+    #[tokio::test]
+    async fn returns_no_signers_for_an_unknown_license() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(ok(json!({ "data": { "developerLicense": null } })));
+        let identity = Identity::new(
+            "https://identity-api.dimo.zone/query",
+            HttpClient::with_transport(transport.clone()),
         );
 
-        self.query(&query).await
+        assert!(identity
+            .developer_license_signers("0xabc")
+            .await
+            .unwrap()
+            .is_empty());
     }
 }
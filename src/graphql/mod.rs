@@ -1,5 +1,201 @@
 mod identity;
-mod telemetry;
+pub mod telemetry;
 
 pub use identity::Identity;
 pub use telemetry::Telemetry;
+
+use crate::utils::DimoError;
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A GraphQL request. User input belongs in `variables` rather than in the query text.
+///
+/// ```ignore
+/// let request = GraphQLRequest::new(
+///     "query Vehicle($tokenId: Int!) { vehicle(tokenId: $tokenId) { owner } }",
+/// )
+/// .with_variable("tokenId", 123)
+/// .with_operation_name("Vehicle");
+/// ```
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+    pub query: String,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<String, Value>,
+    /// Selects the operation to run when `query` defines several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+impl GraphQLRequest {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            variables: Map::new(),
+            operation_name: None,
+        }
+    }
+
+    pub fn with_variable(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.variables.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn with_variables(mut self, variables: Map<String, Value>) -> Self {
+        self.variables.extend(variables);
+        self
+    }
+
+    pub fn with_operation_name(mut self, operation_name: &str) -> Self {
+        self.operation_name = Some(operation_name.to_string());
+        self
+    }
+}
+
+/// A GraphQL response whose `data` is deserialized into `T`.
+///
+/// A query can fail partially, so `data` and `errors` may both be set. Use
+/// [`GraphQLResponse::into_data`] to treat any error as a failure.
+#[derive(Debug, Clone)]
+pub struct GraphQLResponse<T> {
+    pub data: Option<T>,
+    pub errors: Vec<GraphQLError>,
+    /// The `data` of a partially failed query as sent, kept for [`DimoError::GraphQL`].
+    raw_data: Option<Value>,
+}
+
+impl<T: DeserializeOwned> GraphQLResponse<T> {
+    pub fn from_value(mut response: Value) -> Result<Self, DimoError> {
+        let errors: Vec<GraphQLError> = match response.get_mut("errors").map(Value::take) {
+            None | Some(Value::Null) => Vec::new(),
+            Some(errors) => serde_json::from_value(errors)?,
+        };
+        let raw_data = response
+            .get_mut("data")
+            .map(Value::take)
+            .filter(|data| !data.is_null());
+
+        if errors.is_empty() {
+            let data = raw_data.map(serde_json::from_value).transpose()?;
+            return Ok(Self {
+                data,
+                errors,
+                raw_data: None,
+            });
+        }
+        // The partial data of a failed query need not match `T`.
+        let data = raw_data
+            .clone()
+            .and_then(|data| serde_json::from_value(data).ok());
+        Ok(Self {
+            data,
+            errors,
+            raw_data,
+        })
+    }
+}
+
+impl<T> GraphQLResponse<T> {
+    /// The data, or a [`DimoError::GraphQL`] carrying the errors and the partial data as
+    /// JSON when the response has any errors.
+    pub fn into_data(self) -> Result<T, DimoError> {
+        if !self.errors.is_empty() {
+            return Err(DimoError::GraphQL {
+                errors: self.errors,
+                data: self.raw_data,
+            });
+        }
+        self.data
+            .ok_or_else(|| serde_json::Error::custom("graphql response has no data").into())
+    }
+}
+
+/// A single entry of the `errors` array in a GraphQL response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(default)]
+    pub path: Option<Vec<Value>>,
+    #[serde(default)]
+    pub locations: Option<Vec<Value>>,
+    #[serde(default)]
+    pub extensions: Option<Value>,
+}
+
+/// Turns a GraphQL response carrying a non-empty `errors` array into a [`DimoError::GraphQL`].
+fn check_errors(mut response: Value) -> Result<Value, DimoError> {
+    let errors = match response.get_mut("errors").map(Value::take) {
+        Some(Value::Array(errors)) if !errors.is_empty() => errors,
+        _ => return Ok(response),
+    };
+
+    let errors = errors
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<Vec<GraphQLError>, _>>()?;
+    let data = response
+        .get_mut("data")
+        .map(Value::take)
+        .filter(|d| !d.is_null());
+
+    Err(DimoError::GraphQL { errors, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize)]
+    struct Vehicle {
+        #[serde(rename = "tokenId")]
+        token_id: u64,
+    }
+
+    fn partial_failure() -> Value {
+        json!({
+            "data": { "vehicle": null, "other": 1 },
+            "errors": [{ "message": "vehicle not found", "path": ["vehicle"] }],
+        })
+    }
+
+    #[test]
+    fn keeps_the_raw_partial_data_in_the_error() {
+        let response =
+            GraphQLResponse::<HashMap<String, Vehicle>>::from_value(partial_failure()).unwrap();
+        assert!(response.data.is_none());
+        assert_eq!(response.errors.len(), 1);
+
+        match response.into_data() {
+            Err(DimoError::GraphQL { errors, data }) => {
+                assert_eq!(errors[0].message, "vehicle not found");
+                assert_eq!(data, Some(json!({ "vehicle": null, "other": 1 })));
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn returns_the_data_of_a_successful_query() {
+        let response = GraphQLResponse::<HashMap<String, Vehicle>>::from_value(
+            json!({ "data": { "vehicle": { "tokenId": 7 } } }),
+        )
+        .unwrap();
+        assert_eq!(response.into_data().unwrap()["vehicle"].token_id, 7);
+
+        let mismatched =
+            GraphQLResponse::<Vehicle>::from_value(json!({ "data": { "vehicle": {} } }));
+        assert!(matches!(mismatched, Err(DimoError::Json(_))));
+    }
+
+    #[test]
+    fn check_errors_keeps_the_data_too() {
+        match check_errors(partial_failure()) {
+            Err(DimoError::GraphQL { data, .. }) => assert_eq!(data.unwrap()["other"], 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,389 @@
+// Code generated by `cargo run --example generate_signals`. DO NOT EDIT.
+// Source: schema/telemetry.graphqls
+
+use super::signals::SignalType;
+
+/// A vehicle signal that can be queried from the Telemetry API. New signals are added
+/// as the API gains them, so matches on it need a wildcard arm.
+///
+/// See <https://docs.dimo.org/developer-platform/api-references/telemetry-api>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Signal {
+    /// Vehicle rotation rate along Z (vertical).
+    AngularVelocityYaw,
+    /// Rotational speed of a vehicle's wheel.
+    ChassisAxleRow1WheelLeftSpeed,
+    /// Tire pressure in kilo-Pascal.
+    ChassisAxleRow1WheelLeftTirePressure,
+    /// Rotational speed of a vehicle's wheel.
+    ChassisAxleRow1WheelRightSpeed,
+    /// Tire pressure in kilo-Pascal.
+    ChassisAxleRow1WheelRightTirePressure,
+    /// Tire pressure in kilo-Pascal.
+    ChassisAxleRow2WheelLeftTirePressure,
+    /// Tire pressure in kilo-Pascal.
+    ChassisAxleRow2WheelRightTirePressure,
+    /// Current altitude relative to WGS 84 reference ellipsoid, as measured at the position of GNSS
+    /// receiver antenna.
+    CurrentLocationAltitude,
+    /// Current heading relative to geographic north. 0 = North, 90 = East, 180 = South, 270 = West.
+    CurrentLocationHeading,
+    /// Current latitude of vehicle in WGS 84 geodetic coordinates, as measured at the position of
+    /// GNSS receiver antenna.
+    CurrentLocationLatitude,
+    /// Current longitude of vehicle in WGS 84 geodetic coordinates, as measured at the position of
+    /// GNSS receiver antenna.
+    CurrentLocationLongitude,
+    /// Horizontal dilution of precision of GPS.
+    DimoAftermarketHdop,
+    /// Number of sync satellites for GPS.
+    DimoAftermarketNsat,
+    /// Service Set Identifier for the Wi-Fi.
+    DimoAftermarketSsid,
+    /// Indicate the current WPA state for the device's Wi-Fi.
+    DimoAftermarketWpaState,
+    /// Air temperature outside the vehicle.
+    ExteriorAirTemperature,
+    /// Vehicle ignition status. 1 when the ignition is on, 0 otherwise.
+    IsIgnitionOn,
+    /// Current Voltage of the low voltage battery.
+    LowVoltageBatteryCurrentVoltage,
+    /// PID 33 - Barometric pressure.
+    ObdBarometricPressure,
+    /// PID 31 - Distance traveled since codes cleared.
+    ObdDistanceSinceDtcClear,
+    /// PID 21 - Distance traveled with MIL on.
+    ObdDistanceWithMil,
+    /// PID 04 - Engine load in percent - 0 = no load, 100 = full load.
+    ObdEngineLoad,
+    /// PID 0F - Intake temperature.
+    ObdIntakeTemp,
+    /// PID 1F - Engine run time.
+    ObdRunTime,
+    /// Engine coolant temperature.
+    PowertrainCombustionEngineEct,
+    /// Engine oil level.
+    PowertrainCombustionEngineEngineOilLevel,
+    /// Engine oil level as a percentage.
+    PowertrainCombustionEngineEngineOilRelativeLevel,
+    /// Grams of air drawn into engine per second.
+    PowertrainCombustionEngineMaf,
+    /// Engine speed measured as rotations per minute.
+    PowertrainCombustionEngineSpeed,
+    /// Current throttle position.
+    PowertrainCombustionEngineTps,
+    /// Current available fuel in the fuel tank expressed in liters.
+    PowertrainFuelSystemAbsoluteLevel,
+    /// Level in fuel tank as percent of capacity. 0 = empty. 100 = full.
+    PowertrainFuelSystemRelativeLevel,
+    /// High level information of fuel types supported.
+    PowertrainFuelSystemSupportedFuelTypes,
+    /// Remaining range in meters using all energy sources available in the vehicle.
+    PowertrainRange,
+    /// Target charge limit (state of charge) for battery.
+    PowertrainTractionBatteryChargingChargeLimit,
+    /// True if charging is ongoing. Charging is considered to be ongoing if energy is flowing from
+    /// charger to vehicle.
+    PowertrainTractionBatteryChargingIsCharging,
+    /// Current electrical energy flowing in/out of battery. Positive = Energy flowing in to
+    /// battery, e.g. during charging. Negative = Energy flowing out of battery, e.g. during
+    /// driving.
+    PowertrainTractionBatteryCurrentPower,
+    /// Current Voltage of the battery.
+    PowertrainTractionBatteryCurrentVoltage,
+    /// Gross capacity of the battery.
+    PowertrainTractionBatteryGrossCapacity,
+    /// Physical state of charge of the high voltage battery, relative to net capacity. This is not
+    /// necessarily the state of charge being displayed to the customer.
+    PowertrainTractionBatteryStateOfChargeCurrent,
+    /// Current average temperature of the battery cells.
+    PowertrainTractionBatteryTemperatureAverage,
+    /// Odometer reading, total distance travelled during the lifetime of the transmission.
+    PowertrainTransmissionTravelledDistance,
+    /// Defines the powertrain type of the vehicle.
+    PowertrainType,
+    /// Vehicle speed.
+    Speed,
+}
+
+impl Signal {
+    pub const ALL: [Signal; 44] = [
+        Signal::AngularVelocityYaw,
+        Signal::ChassisAxleRow1WheelLeftSpeed,
+        Signal::ChassisAxleRow1WheelLeftTirePressure,
+        Signal::ChassisAxleRow1WheelRightSpeed,
+        Signal::ChassisAxleRow1WheelRightTirePressure,
+        Signal::ChassisAxleRow2WheelLeftTirePressure,
+        Signal::ChassisAxleRow2WheelRightTirePressure,
+        Signal::CurrentLocationAltitude,
+        Signal::CurrentLocationHeading,
+        Signal::CurrentLocationLatitude,
+        Signal::CurrentLocationLongitude,
+        Signal::DimoAftermarketHdop,
+        Signal::DimoAftermarketNsat,
+        Signal::DimoAftermarketSsid,
+        Signal::DimoAftermarketWpaState,
+        Signal::ExteriorAirTemperature,
+        Signal::IsIgnitionOn,
+        Signal::LowVoltageBatteryCurrentVoltage,
+        Signal::ObdBarometricPressure,
+        Signal::ObdDistanceSinceDtcClear,
+        Signal::ObdDistanceWithMil,
+        Signal::ObdEngineLoad,
+        Signal::ObdIntakeTemp,
+        Signal::ObdRunTime,
+        Signal::PowertrainCombustionEngineEct,
+        Signal::PowertrainCombustionEngineEngineOilLevel,
+        Signal::PowertrainCombustionEngineEngineOilRelativeLevel,
+        Signal::PowertrainCombustionEngineMaf,
+        Signal::PowertrainCombustionEngineSpeed,
+        Signal::PowertrainCombustionEngineTps,
+        Signal::PowertrainFuelSystemAbsoluteLevel,
+        Signal::PowertrainFuelSystemRelativeLevel,
+        Signal::PowertrainFuelSystemSupportedFuelTypes,
+        Signal::PowertrainRange,
+        Signal::PowertrainTractionBatteryChargingChargeLimit,
+        Signal::PowertrainTractionBatteryChargingIsCharging,
+        Signal::PowertrainTractionBatteryCurrentPower,
+        Signal::PowertrainTractionBatteryCurrentVoltage,
+        Signal::PowertrainTractionBatteryGrossCapacity,
+        Signal::PowertrainTractionBatteryStateOfChargeCurrent,
+        Signal::PowertrainTractionBatteryTemperatureAverage,
+        Signal::PowertrainTransmissionTravelledDistance,
+        Signal::PowertrainType,
+        Signal::Speed,
+    ];
+
+    /// The field name in the Telemetry API schema, e.g. `powertrainRange`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::AngularVelocityYaw => "angularVelocityYaw",
+            Signal::ChassisAxleRow1WheelLeftSpeed => "chassisAxleRow1WheelLeftSpeed",
+            Signal::ChassisAxleRow1WheelLeftTirePressure => "chassisAxleRow1WheelLeftTirePressure",
+            Signal::ChassisAxleRow1WheelRightSpeed => "chassisAxleRow1WheelRightSpeed",
+            Signal::ChassisAxleRow1WheelRightTirePressure => {
+                "chassisAxleRow1WheelRightTirePressure"
+            }
+            Signal::ChassisAxleRow2WheelLeftTirePressure => "chassisAxleRow2WheelLeftTirePressure",
+            Signal::ChassisAxleRow2WheelRightTirePressure => {
+                "chassisAxleRow2WheelRightTirePressure"
+            }
+            Signal::CurrentLocationAltitude => "currentLocationAltitude",
+            Signal::CurrentLocationHeading => "currentLocationHeading",
+            Signal::CurrentLocationLatitude => "currentLocationLatitude",
+            Signal::CurrentLocationLongitude => "currentLocationLongitude",
+            Signal::DimoAftermarketHdop => "dimoAftermarketHDOP",
+            Signal::DimoAftermarketNsat => "dimoAftermarketNSAT",
+            Signal::DimoAftermarketSsid => "dimoAftermarketSSID",
+            Signal::DimoAftermarketWpaState => "dimoAftermarketWPAState",
+            Signal::ExteriorAirTemperature => "exteriorAirTemperature",
+            Signal::IsIgnitionOn => "isIgnitionOn",
+            Signal::LowVoltageBatteryCurrentVoltage => "lowVoltageBatteryCurrentVoltage",
+            Signal::ObdBarometricPressure => "obdBarometricPressure",
+            Signal::ObdDistanceSinceDtcClear => "obdDistanceSinceDTCClear",
+            Signal::ObdDistanceWithMil => "obdDistanceWithMIL",
+            Signal::ObdEngineLoad => "obdEngineLoad",
+            Signal::ObdIntakeTemp => "obdIntakeTemp",
+            Signal::ObdRunTime => "obdRunTime",
+            Signal::PowertrainCombustionEngineEct => "powertrainCombustionEngineECT",
+            Signal::PowertrainCombustionEngineEngineOilLevel => {
+                "powertrainCombustionEngineEngineOilLevel"
+            }
+            Signal::PowertrainCombustionEngineEngineOilRelativeLevel => {
+                "powertrainCombustionEngineEngineOilRelativeLevel"
+            }
+            Signal::PowertrainCombustionEngineMaf => "powertrainCombustionEngineMAF",
+            Signal::PowertrainCombustionEngineSpeed => "powertrainCombustionEngineSpeed",
+            Signal::PowertrainCombustionEngineTps => "powertrainCombustionEngineTPS",
+            Signal::PowertrainFuelSystemAbsoluteLevel => "powertrainFuelSystemAbsoluteLevel",
+            Signal::PowertrainFuelSystemRelativeLevel => "powertrainFuelSystemRelativeLevel",
+            Signal::PowertrainFuelSystemSupportedFuelTypes => {
+                "powertrainFuelSystemSupportedFuelTypes"
+            }
+            Signal::PowertrainRange => "powertrainRange",
+            Signal::PowertrainTractionBatteryChargingChargeLimit => {
+                "powertrainTractionBatteryChargingChargeLimit"
+            }
+            Signal::PowertrainTractionBatteryChargingIsCharging => {
+                "powertrainTractionBatteryChargingIsCharging"
+            }
+            Signal::PowertrainTractionBatteryCurrentPower => {
+                "powertrainTractionBatteryCurrentPower"
+            }
+            Signal::PowertrainTractionBatteryCurrentVoltage => {
+                "powertrainTractionBatteryCurrentVoltage"
+            }
+            Signal::PowertrainTractionBatteryGrossCapacity => {
+                "powertrainTractionBatteryGrossCapacity"
+            }
+            Signal::PowertrainTractionBatteryStateOfChargeCurrent => {
+                "powertrainTractionBatteryStateOfChargeCurrent"
+            }
+            Signal::PowertrainTractionBatteryTemperatureAverage => {
+                "powertrainTractionBatteryTemperatureAverage"
+            }
+            Signal::PowertrainTransmissionTravelledDistance => {
+                "powertrainTransmissionTravelledDistance"
+            }
+            Signal::PowertrainType => "powertrainType",
+            Signal::Speed => "speed",
+        }
+    }
+
+    pub fn value_type(self) -> SignalType {
+        match self {
+            Signal::DimoAftermarketSsid
+            | Signal::DimoAftermarketWpaState
+            | Signal::PowertrainCombustionEngineEngineOilLevel
+            | Signal::PowertrainFuelSystemSupportedFuelTypes
+            | Signal::PowertrainType => SignalType::String,
+            _ => SignalType::Float,
+        }
+    }
+
+    /// The unit of the signal's values, e.g. `km/h`, if it has one.
+    pub fn unit(self) -> Option<&'static str> {
+        match self {
+            Signal::AngularVelocityYaw => Some("degrees/s"),
+            Signal::ChassisAxleRow1WheelLeftSpeed => Some("km/h"),
+            Signal::ChassisAxleRow1WheelLeftTirePressure => Some("kPa"),
+            Signal::ChassisAxleRow1WheelRightSpeed => Some("km/h"),
+            Signal::ChassisAxleRow1WheelRightTirePressure => Some("kPa"),
+            Signal::ChassisAxleRow2WheelLeftTirePressure => Some("kPa"),
+            Signal::ChassisAxleRow2WheelRightTirePressure => Some("kPa"),
+            Signal::CurrentLocationAltitude => Some("m"),
+            Signal::CurrentLocationHeading => Some("degrees"),
+            Signal::CurrentLocationLatitude => Some("degrees"),
+            Signal::CurrentLocationLongitude => Some("degrees"),
+            Signal::DimoAftermarketHdop => None,
+            Signal::DimoAftermarketNsat => None,
+            Signal::DimoAftermarketSsid => None,
+            Signal::DimoAftermarketWpaState => None,
+            Signal::ExteriorAirTemperature => Some("celsius"),
+            Signal::IsIgnitionOn => None,
+            Signal::LowVoltageBatteryCurrentVoltage => Some("V"),
+            Signal::ObdBarometricPressure => Some("kPa"),
+            Signal::ObdDistanceSinceDtcClear => Some("km"),
+            Signal::ObdDistanceWithMil => Some("km"),
+            Signal::ObdEngineLoad => Some("percent"),
+            Signal::ObdIntakeTemp => Some("celsius"),
+            Signal::ObdRunTime => Some("s"),
+            Signal::PowertrainCombustionEngineEct => Some("celsius"),
+            Signal::PowertrainCombustionEngineEngineOilLevel => None,
+            Signal::PowertrainCombustionEngineEngineOilRelativeLevel => Some("percent"),
+            Signal::PowertrainCombustionEngineMaf => Some("g/s"),
+            Signal::PowertrainCombustionEngineSpeed => Some("rpm"),
+            Signal::PowertrainCombustionEngineTps => Some("percent"),
+            Signal::PowertrainFuelSystemAbsoluteLevel => Some("l"),
+            Signal::PowertrainFuelSystemRelativeLevel => Some("percent"),
+            Signal::PowertrainFuelSystemSupportedFuelTypes => None,
+            Signal::PowertrainRange => Some("m"),
+            Signal::PowertrainTractionBatteryChargingChargeLimit => Some("percent"),
+            Signal::PowertrainTractionBatteryChargingIsCharging => None,
+            Signal::PowertrainTractionBatteryCurrentPower => Some("W"),
+            Signal::PowertrainTractionBatteryCurrentVoltage => Some("V"),
+            Signal::PowertrainTractionBatteryGrossCapacity => Some("kWh"),
+            Signal::PowertrainTractionBatteryStateOfChargeCurrent => Some("percent"),
+            Signal::PowertrainTractionBatteryTemperatureAverage => Some("celsius"),
+            Signal::PowertrainTransmissionTravelledDistance => Some("km"),
+            Signal::PowertrainType => None,
+            Signal::Speed => Some("km/h"),
+        }
+    }
+
+    /// The path of the signal in the Vehicle Signal Specification, e.g. `Vehicle.Speed`.
+    pub fn vss_path(self) -> &'static str {
+        match self {
+            Signal::AngularVelocityYaw => "Vehicle.AngularVelocity.Yaw",
+            Signal::ChassisAxleRow1WheelLeftSpeed => "Vehicle.Chassis.Axle.Row1.Wheel.Left.Speed",
+            Signal::ChassisAxleRow1WheelLeftTirePressure => {
+                "Vehicle.Chassis.Axle.Row1.Wheel.Left.Tire.Pressure"
+            }
+            Signal::ChassisAxleRow1WheelRightSpeed => "Vehicle.Chassis.Axle.Row1.Wheel.Right.Speed",
+            Signal::ChassisAxleRow1WheelRightTirePressure => {
+                "Vehicle.Chassis.Axle.Row1.Wheel.Right.Tire.Pressure"
+            }
+            Signal::ChassisAxleRow2WheelLeftTirePressure => {
+                "Vehicle.Chassis.Axle.Row2.Wheel.Left.Tire.Pressure"
+            }
+            Signal::ChassisAxleRow2WheelRightTirePressure => {
+                "Vehicle.Chassis.Axle.Row2.Wheel.Right.Tire.Pressure"
+            }
+            Signal::CurrentLocationAltitude => "Vehicle.CurrentLocation.Altitude",
+            Signal::CurrentLocationHeading => "Vehicle.CurrentLocation.Heading",
+            Signal::CurrentLocationLatitude => "Vehicle.CurrentLocation.Latitude",
+            Signal::CurrentLocationLongitude => "Vehicle.CurrentLocation.Longitude",
+            Signal::DimoAftermarketHdop => "Vehicle.DIMO.Aftermarket.HDOP",
+            Signal::DimoAftermarketNsat => "Vehicle.DIMO.Aftermarket.NSAT",
+            Signal::DimoAftermarketSsid => "Vehicle.DIMO.Aftermarket.SSID",
+            Signal::DimoAftermarketWpaState => "Vehicle.DIMO.Aftermarket.WPAState",
+            Signal::ExteriorAirTemperature => "Vehicle.Exterior.AirTemperature",
+            Signal::IsIgnitionOn => "Vehicle.IsIgnitionOn",
+            Signal::LowVoltageBatteryCurrentVoltage => "Vehicle.LowVoltageBattery.CurrentVoltage",
+            Signal::ObdBarometricPressure => "Vehicle.OBD.BarometricPressure",
+            Signal::ObdDistanceSinceDtcClear => "Vehicle.OBD.DistanceSinceDTCClear",
+            Signal::ObdDistanceWithMil => "Vehicle.OBD.DistanceWithMIL",
+            Signal::ObdEngineLoad => "Vehicle.OBD.EngineLoad",
+            Signal::ObdIntakeTemp => "Vehicle.OBD.IntakeTemp",
+            Signal::ObdRunTime => "Vehicle.OBD.RunTime",
+            Signal::PowertrainCombustionEngineEct => "Vehicle.Powertrain.CombustionEngine.ECT",
+            Signal::PowertrainCombustionEngineEngineOilLevel => {
+                "Vehicle.Powertrain.CombustionEngine.EngineOilLevel"
+            }
+            Signal::PowertrainCombustionEngineEngineOilRelativeLevel => {
+                "Vehicle.Powertrain.CombustionEngine.EngineOilRelativeLevel"
+            }
+            Signal::PowertrainCombustionEngineMaf => "Vehicle.Powertrain.CombustionEngine.MAF",
+            Signal::PowertrainCombustionEngineSpeed => "Vehicle.Powertrain.CombustionEngine.Speed",
+            Signal::PowertrainCombustionEngineTps => "Vehicle.Powertrain.CombustionEngine.TPS",
+            Signal::PowertrainFuelSystemAbsoluteLevel => {
+                "Vehicle.Powertrain.FuelSystem.AbsoluteLevel"
+            }
+            Signal::PowertrainFuelSystemRelativeLevel => {
+                "Vehicle.Powertrain.FuelSystem.RelativeLevel"
+            }
+            Signal::PowertrainFuelSystemSupportedFuelTypes => {
+                "Vehicle.Powertrain.FuelSystem.SupportedFuelTypes"
+            }
+            Signal::PowertrainRange => "Vehicle.Powertrain.Range",
+            Signal::PowertrainTractionBatteryChargingChargeLimit => {
+                "Vehicle.Powertrain.TractionBattery.Charging.ChargeLimit"
+            }
+            Signal::PowertrainTractionBatteryChargingIsCharging => {
+                "Vehicle.Powertrain.TractionBattery.Charging.IsCharging"
+            }
+            Signal::PowertrainTractionBatteryCurrentPower => {
+                "Vehicle.Powertrain.TractionBattery.CurrentPower"
+            }
+            Signal::PowertrainTractionBatteryCurrentVoltage => {
+                "Vehicle.Powertrain.TractionBattery.CurrentVoltage"
+            }
+            Signal::PowertrainTractionBatteryGrossCapacity => {
+                "Vehicle.Powertrain.TractionBattery.GrossCapacity"
+            }
+            Signal::PowertrainTractionBatteryStateOfChargeCurrent => {
+                "Vehicle.Powertrain.TractionBattery.StateOfCharge.Current"
+            }
+            Signal::PowertrainTractionBatteryTemperatureAverage => {
+                "Vehicle.Powertrain.TractionBattery.Temperature.Average"
+            }
+            Signal::PowertrainTransmissionTravelledDistance => {
+                "Vehicle.Powertrain.Transmission.TravelledDistance"
+            }
+            Signal::PowertrainType => "Vehicle.Powertrain.Type",
+            Signal::Speed => "Vehicle.Speed",
+        }
+    }
+
+    /// Whether reading the signal needs [`crate::Privilege::AllTimeLocation`].
+    pub fn is_location(self) -> bool {
+        matches!(
+            self,
+            Signal::CurrentLocationAltitude
+                | Signal::CurrentLocationHeading
+                | Signal::CurrentLocationLatitude
+                | Signal::CurrentLocationLongitude
+        )
+    }
+}
@@ -0,0 +1,8 @@
+pub mod catalog;
+pub mod models;
+pub mod signals;
+#[allow(clippy::module_inception)]
+pub mod telemetry;
+pub use models::{LatestSignals, SignalPoint, SignalSeries, SignalValue};
+pub use signals::{Aggregation, Signal, SignalType};
+pub use telemetry::Telemetry;
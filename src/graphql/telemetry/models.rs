@@ -0,0 +1,79 @@
+use super::signals::{Aggregation, Signal};
+use chrono::DateTime;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// A signal value; its variant follows [`Signal::value_type`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum SignalValue {
+    Float(f64),
+    String(String),
+}
+
+impl SignalValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            SignalValue::Float(value) => Some(*value),
+            SignalValue::String(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SignalValue::Float(_) => None,
+            SignalValue::String(value) => Some(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SignalPoint {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub timestamp: SystemTime,
+    pub value: SignalValue,
+}
+
+/// The aggregated values of one signal returned by [`super::Telemetry::signals`], one
+/// point per interval that has data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalSeries {
+    pub signal: Signal,
+    pub aggregation: Aggregation,
+    pub points: Vec<SignalPoint>,
+}
+
+/// The result of [`super::Telemetry::signals_latest`]. Signals without any data are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatestSignals {
+    /// When the vehicle last sent any data.
+    pub last_seen: Option<SystemTime>,
+    pub signals: HashMap<Signal, SignalPoint>,
+}
+
+impl LatestSignals {
+    pub fn get(&self, signal: Signal) -> Option<&SignalPoint> {
+        self.signals.get(&signal)
+    }
+}
+
+/// Deserializes an RFC 3339 `Time` scalar.
+pub(crate) fn deserialize_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<SystemTime, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&value)
+        .map(SystemTime::from)
+        .map_err(|e| serde::de::Error::custom(format!("invalid time '{}': {}", value, e)))
+}
+
+pub(crate) fn deserialize_optional_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<SystemTime>, D::Error> {
+    #[derive(Deserialize)]
+    struct Time(#[serde(deserialize_with = "deserialize_time")] SystemTime);
+
+    let time: Option<Time> = Option::deserialize(deserializer)?;
+    Ok(time.map(|Time(time)| time))
+}
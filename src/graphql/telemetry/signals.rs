@@ -0,0 +1,68 @@
+pub use super::catalog::Signal;
+use std::fmt;
+
+/// The GraphQL type of a [`Signal`]'s values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignalType {
+    Float,
+    String,
+}
+
+impl Signal {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How the values of a [`Signal`] are combined within each interval of
+/// [`super::Telemetry::signals`].
+///
+/// String signals only support [`Aggregation::First`], [`Aggregation::Last`] and
+/// [`Aggregation::Rand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aggregation {
+    Avg,
+    Max,
+    Min,
+    Med,
+    First,
+    Last,
+    Rand,
+}
+
+impl Aggregation {
+    /// The `FloatAggregation` or `StringAggregation` enum value, e.g. `AVG`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Aggregation::Avg => "AVG",
+            Aggregation::Max => "MAX",
+            Aggregation::Min => "MIN",
+            Aggregation::Med => "MED",
+            Aggregation::First => "FIRST",
+            Aggregation::Last => "LAST",
+            Aggregation::Rand => "RAND",
+        }
+    }
+
+    pub fn supports(self, value_type: SignalType) -> bool {
+        match value_type {
+            SignalType::Float => true,
+            SignalType::String => matches!(
+                self,
+                Aggregation::First | Aggregation::Last | Aggregation::Rand
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,535 @@
+use super::models::{
+    deserialize_optional_time, deserialize_time, LatestSignals, SignalPoint, SignalSeries,
+};
+use super::signals::{Aggregation, Signal};
+use crate::graphql::{check_errors, GraphQLRequest, GraphQLResponse};
+use crate::rest::tokenexchange::Privilege;
+use crate::utils::request::{to_body, AuthRequestParams, HttpClient, TokenType};
+use crate::utils::DimoError;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Privileges needed for queries that read no location signals.
+const NON_LOCATION_PRIVILEGES: &[Privilege] = &[Privilege::AllTimeNonLocationData];
+/// Privileges needed for queries that read location signals.
+const LOCATION_PRIVILEGES: &[Privilege] = &[
+    Privilege::AllTimeNonLocationData,
+    Privilege::AllTimeLocation,
+];
+
+pub struct Telemetry {
+    base_url: String,
+    client: HttpClient,
+}
+
+impl Telemetry {
+    pub fn new(base_url: &str, client: HttpClient) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            client,
+        }
+    }
+
+    /// Sends `query` authenticated with a vehicle JWT for `token_id`.
+    ///
+    /// The JWT only grants [`Privilege::AllTimeNonLocationData`]; use
+    /// [`Telemetry::query_with_privileges`] for queries that read location signals.
+    pub async fn query(&self, token_id: &str, query: &str) -> Result<Value, DimoError> {
+        self.query_with_privileges(token_id, query, NON_LOCATION_PRIVILEGES)
+            .await
+    }
+
+    /// Sends `query` authenticated with a vehicle JWT for `token_id` granting `privileges`.
+    pub async fn query_with_privileges(
+        &self,
+        token_id: &str,
+        query: &str,
+        privileges: &[Privilege],
+    ) -> Result<Value, DimoError> {
+        let response = self
+            .execute_raw(token_id, &GraphQLRequest::new(query), privileges)
+            .await?;
+        check_errors(response)
+    }
+
+    /// Sends `request` authenticated with a vehicle JWT for `token_id` and deserializes its
+    /// `data` into `T`. Like [`Telemetry::query`], the JWT only grants
+    /// [`Privilege::AllTimeNonLocationData`].
+    pub async fn execute<T: DeserializeOwned>(
+        &self,
+        token_id: &str,
+        request: &GraphQLRequest,
+    ) -> Result<GraphQLResponse<T>, DimoError> {
+        self.execute_with_privileges(token_id, request, NON_LOCATION_PRIVILEGES)
+            .await
+    }
+
+    pub async fn execute_with_privileges<T: DeserializeOwned>(
+        &self,
+        token_id: &str,
+        request: &GraphQLRequest,
+        privileges: &[Privilege],
+    ) -> Result<GraphQLResponse<T>, DimoError> {
+        let response = self.execute_raw(token_id, request, privileges).await?;
+        GraphQLResponse::from_value(response)
+    }
+
+    /// Like [`Telemetry::execute_with_privileges`], but returns the whole response as JSON.
+    pub async fn execute_raw(
+        &self,
+        token_id: &str,
+        request: &GraphQLRequest,
+        privileges: &[Privilege],
+    ) -> Result<Value, DimoError> {
+        let params = AuthRequestParams {
+            method: reqwest::Method::POST,
+            idempotent: true,
+            base_url: self.base_url.clone(),
+            path: "".to_string(),
+            query_params: None,
+            body: Some(to_body(request)?),
+            headers: None,
+            token_type: TokenType::vehicle(token_id, privileges)?,
+        };
+
+        self.client.make_auth_request(params).await
+    }
+
+    pub async fn get_latest_signals(&self, token_id: &str) -> Result<Value, DimoError> {
+        let query = r#"
+            query LatestSignals($tokenId: Int!) {
+                signalsLatest(tokenId: $tokenId) {
+                    powertrainTransmissionTravelledDistance {
+                        timestamp
+                        value
+                    }
+                    exteriorAirTemperature {
+                        timestamp
+                        value
+                    }
+                    speed {
+                        timestamp
+                        value
+                    }
+                    powertrainType {
+                        timestamp
+                        value
+                    }
+                }
+            }
+        "#;
+        let request =
+            GraphQLRequest::new(query).with_variable("tokenId", parse_token_id(token_id)?);
+
+        let response = self
+            .execute_raw(token_id, &request, NON_LOCATION_PRIVILEGES)
+            .await?;
+        check_errors(response)
+    }
+
+    /// The `signals` of `token_id` between `from` and `to`, aggregated over each `interval`.
+    ///
+    /// Returns one series per requested signal and aggregation, in the order requested. The
+    /// same signal may be requested with several aggregations.
+    ///
+    /// ```ignore
+    /// let series = dimo
+    ///     .telemetry
+    ///     .signals(
+    ///         "123",
+    ///         SystemTime::now() - Duration::from_secs(86400),
+    ///         SystemTime::now(),
+    ///         Duration::from_secs(3600),
+    ///         &[(Signal::Speed, Aggregation::Max), (Signal::Speed, Aggregation::Avg)],
+    ///     )
+    ///     .await?;
+    /// ```
+    pub async fn signals(
+        &self,
+        token_id: &str,
+        from: SystemTime,
+        to: SystemTime,
+        interval: Duration,
+        signals: &[(Signal, Aggregation)],
+    ) -> Result<Vec<SignalSeries>, DimoError> {
+        if signals.is_empty() {
+            return Err(DimoError::Config("no signals requested".to_string()));
+        }
+        if interval.is_zero() {
+            return Err(DimoError::Config(
+                "signal interval must not be zero".to_string(),
+            ));
+        }
+        let mut fields = String::new();
+        for (i, (signal, aggregation)) in signals.iter().enumerate() {
+            if !aggregation.supports(signal.value_type()) {
+                return Err(DimoError::Config(format!(
+                    "aggregation {} is not supported by signal {}",
+                    aggregation, signal
+                )));
+            }
+            fields.push_str(&format!("s{}: {}(agg: {})\n", i, signal, aggregation));
+        }
+        let query = format!(
+            r#"
+            query Signals($tokenId: Int!, $from: Time!, $to: Time!, $interval: String!) {{
+                signals(tokenId: $tokenId, from: $from, to: $to, interval: $interval) {{
+                    timestamp
+                    {fields}
+                }}
+            }}
+            "#,
+            fields = fields
+        );
+        let request = GraphQLRequest::new(&query)
+            .with_variable("tokenId", parse_token_id(token_id)?)
+            .with_variable("from", format_time(from))
+            .with_variable("to", format_time(to))
+            .with_variable("interval", format_interval(interval))
+            .with_operation_name("Signals");
+
+        let privileges = privileges_for(signals.iter().map(|(signal, _)| *signal));
+        let data: SignalsData = self
+            .execute_with_privileges(token_id, &request, privileges)
+            .await?
+            .into_data()?;
+
+        let mut series: Vec<SignalSeries> = signals
+            .iter()
+            .map(|(signal, aggregation)| SignalSeries {
+                signal: *signal,
+                aggregation: *aggregation,
+                points: Vec::new(),
+            })
+            .collect();
+        for mut bucket in data.signals.unwrap_or_default() {
+            for (i, series) in series.iter_mut().enumerate() {
+                match bucket.values.remove(&format!("s{}", i)) {
+                    None | Some(Value::Null) => {}
+                    Some(value) => series.points.push(SignalPoint {
+                        timestamp: bucket.timestamp,
+                        value: serde_json::from_value(value)?,
+                    }),
+                }
+            }
+        }
+        Ok(series)
+    }
+
+    /// The latest value of each of `signals` for `token_id`.
+    pub async fn signals_latest(
+        &self,
+        token_id: &str,
+        signals: &[Signal],
+    ) -> Result<LatestSignals, DimoError> {
+        let fields: String = signals
+            .iter()
+            .map(|signal| format!("{} {{ timestamp value }}\n", signal))
+            .collect();
+        let query = format!(
+            r#"
+            query LatestSignals($tokenId: Int!) {{
+                signalsLatest(tokenId: $tokenId) {{
+                    lastSeen
+                    {fields}
+                }}
+            }}
+            "#,
+            fields = fields
+        );
+        let request = GraphQLRequest::new(&query)
+            .with_variable("tokenId", parse_token_id(token_id)?)
+            .with_operation_name("LatestSignals");
+
+        let privileges = privileges_for(signals.iter().copied());
+        let data: LatestSignalsData = self
+            .execute_with_privileges(token_id, &request, privileges)
+            .await?
+            .into_data()?;
+
+        let Some(mut latest) = data.signals_latest else {
+            return Ok(LatestSignals {
+                last_seen: None,
+                signals: HashMap::new(),
+            });
+        };
+        let mut points = HashMap::new();
+        for signal in signals {
+            match latest.values.remove(signal.name()) {
+                None | Some(Value::Null) => {}
+                Some(point) => {
+                    points.insert(*signal, serde_json::from_value(point)?);
+                }
+            }
+        }
+        Ok(LatestSignals {
+            last_seen: latest.last_seen,
+            signals: points,
+        })
+    }
+
+    /// The signals that have data for `token_id`. Signals this SDK does not know about are
+    /// left out.
+    pub async fn available_signals(&self, token_id: &str) -> Result<Vec<Signal>, DimoError> {
+        let query = r#"
+            query AvailableSignals($tokenId: Int!) {
+                availableSignals(tokenId: $tokenId)
+            }
+        "#;
+        let request =
+            GraphQLRequest::new(query).with_variable("tokenId", parse_token_id(token_id)?);
+
+        let data: AvailableSignalsData = self.execute(token_id, &request).await?.into_data()?;
+        Ok(data
+            .available_signals
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|name| Signal::from_name(name))
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct SignalsData {
+    signals: Option<Vec<SignalBucket>>,
+}
+
+#[derive(Deserialize)]
+struct SignalBucket {
+    #[serde(deserialize_with = "deserialize_time")]
+    timestamp: SystemTime,
+    #[serde(flatten)]
+    values: HashMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestSignalsData {
+    signals_latest: Option<SignalCollection>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignalCollection {
+    #[serde(default, deserialize_with = "deserialize_optional_time")]
+    last_seen: Option<SystemTime>,
+    #[serde(flatten)]
+    values: HashMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AvailableSignalsData {
+    available_signals: Option<Vec<String>>,
+}
+
+/// The privileges needed to read `signals`.
+fn privileges_for(mut signals: impl Iterator<Item = Signal>) -> &'static [Privilege] {
+    if signals.any(Signal::is_location) {
+        LOCATION_PRIVILEGES
+    } else {
+        NON_LOCATION_PRIVILEGES
+    }
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Formats `interval` as a Go duration string such as `3600s`.
+fn format_interval(interval: Duration) -> String {
+    if interval.subsec_nanos() == 0 {
+        format!("{}s", interval.as_secs())
+    } else {
+        format!("{}ms", interval.as_millis())
+    }
+}
+
+/// The `Int` token ID variable for `token_id` as it appears in API paths.
+fn parse_token_id(token_id: &str) -> Result<i32, DimoError> {
+    token_id
+        .parse()
+        .map_err(|_| DimoError::Config(format!("invalid vehicle token id '{}'", token_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphql::telemetry::SignalValue;
+    use crate::utils::testing::{self, bearer, body, ok, vehicle_token_response};
+    use crate::utils::transport::MockTransport;
+    use reqwest::Method;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn at(time: &str) -> SystemTime {
+        DateTime::parse_from_rfc3339(time).unwrap().into()
+    }
+
+    #[test]
+    fn formats_intervals_as_go_durations() {
+        assert_eq!(format_interval(Duration::from_secs(3600)), "3600s");
+        assert_eq!(format_interval(Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[test]
+    fn asks_for_location_privileges_only_for_location_signals() {
+        assert_eq!(
+            privileges_for([Signal::Speed, Signal::PowertrainType].into_iter()),
+            NON_LOCATION_PRIVILEGES
+        );
+        assert_eq!(
+            privileges_for([Signal::Speed, Signal::CurrentLocationLatitude].into_iter()),
+            LOCATION_PRIVILEGES
+        );
+    }
+
+    #[tokio::test]
+    async fn queries_aliased_aggregations_and_splits_them_into_series() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Method::POST,
+            "/v1/tokens/exchange",
+            vehicle_token_response(7, &[1]),
+        );
+        transport.on(
+            Method::POST,
+            "/query",
+            ok(json!({ "data": { "signals": [
+                {
+                    "timestamp": "2024-03-04T10:00:00Z",
+                    "s0": 88.5,
+                    "s1": 41.25,
+                    "s2": "ICE",
+                },
+                {
+                    "timestamp": "2024-03-04T11:00:00Z",
+                    "s0": null,
+                    "s1": 12,
+                    "s2": "ICE",
+                },
+            ] } })),
+        );
+        let dimo = testing::dimo(&transport).await;
+
+        let series = dimo
+            .telemetry
+            .signals(
+                "7",
+                at("2024-03-04T10:00:00Z"),
+                at("2024-03-04T12:00:00Z"),
+                Duration::from_secs(3600),
+                &[
+                    (Signal::Speed, Aggregation::Max),
+                    (Signal::Speed, Aggregation::Avg),
+                    (Signal::PowertrainType, Aggregation::Rand),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(body(&requests[0])["privileges"], json!([1]));
+        let sent = body(&requests[1]);
+        // The query is sent with the vehicle JWT, not the developer JWT.
+        assert_ne!(bearer(&requests[1]), "Bearer DEV");
+        assert_eq!(sent["operationName"], "Signals");
+        let query = sent["query"].as_str().unwrap();
+        assert!(query.contains("s0: speed(agg: MAX)"));
+        assert!(query.contains("s1: speed(agg: AVG)"));
+        assert!(query.contains("s2: powertrainType(agg: RAND)"));
+        assert_eq!(
+            sent["variables"],
+            json!({
+                "tokenId": 7,
+                "from": "2024-03-04T10:00:00Z",
+                "to": "2024-03-04T12:00:00Z",
+                "interval": "3600s",
+            })
+        );
+
+        assert_eq!(series.len(), 3);
+        assert_eq!(
+            (series[0].signal, series[0].aggregation),
+            (Signal::Speed, Aggregation::Max)
+        );
+        // A null value leaves a gap rather than a point.
+        assert_eq!(series[0].points.len(), 1);
+        assert_eq!(series[0].points[0].value, SignalValue::Float(88.5));
+        assert_eq!(
+            series[1]
+                .points
+                .iter()
+                .map(|p| p.value.as_f64().unwrap())
+                .collect::<Vec<_>>(),
+            [41.25, 12.0]
+        );
+        assert_eq!(series[1].points[1].timestamp, at("2024-03-04T11:00:00Z"));
+        assert_eq!(series[2].points[0].value.as_str(), Some("ICE"));
+    }
+
+    #[tokio::test]
+    async fn rejects_float_aggregations_of_string_signals() {
+        let transport = Arc::new(MockTransport::new());
+        let dimo = testing::dimo(&transport).await;
+
+        let err = dimo
+            .telemetry
+            .signals(
+                "7",
+                at("2024-03-04T10:00:00Z"),
+                at("2024-03-04T12:00:00Z"),
+                Duration::from_secs(3600),
+                &[(Signal::PowertrainType, Aggregation::Avg)],
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DimoError::Config(_)));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reads_latest_location_signals_with_the_location_privilege() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Method::POST,
+            "/v1/tokens/exchange",
+            vehicle_token_response(7, &[1, 4]),
+        );
+        transport.on(
+            Method::POST,
+            "/query",
+            ok(json!({ "data": { "signalsLatest": {
+                "lastSeen": "2024-03-04T11:59:30Z",
+                "currentLocationLatitude": {
+                    "timestamp": "2024-03-04T11:59:00Z",
+                    "value": 37.7749,
+                },
+                "speed": null,
+            } } })),
+        );
+        let dimo = testing::dimo(&transport).await;
+
+        let latest = dimo
+            .telemetry
+            .signals_latest("7", &[Signal::CurrentLocationLatitude, Signal::Speed])
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(body(&requests[0])["privileges"], json!([1, 4]));
+        let query = body(&requests[1])["query"].as_str().unwrap().to_string();
+        assert!(query.contains("currentLocationLatitude { timestamp value }"));
+        assert!(query.contains("speed { timestamp value }"));
+
+        assert_eq!(latest.last_seen, Some(at("2024-03-04T11:59:30Z")));
+        let latitude = latest.get(Signal::CurrentLocationLatitude).unwrap();
+        assert_eq!(latitude.value.as_f64(), Some(37.7749));
+        assert_eq!(latitude.timestamp, at("2024-03-04T11:59:00Z"));
+        assert!(latest.get(Signal::Speed).is_none());
+    }
+}
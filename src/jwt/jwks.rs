@@ -0,0 +1,57 @@
+use crate::utils::request::{HttpClient, RequestParams};
+use crate::utils::DimoError;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use reqwest::Method;
+use std::path::Path;
+
+/// A JSON Web Key Set holding the public keys DIMO signs its JWTs with.
+#[derive(Debug, Clone)]
+pub struct Jwks {
+    set: JwkSet,
+}
+
+impl Jwks {
+    pub fn from_json(json: &str) -> Result<Self, DimoError> {
+        let set = serde_json::from_str(json)?;
+        Ok(Self { set })
+    }
+
+    /// Loads a JWKS document saved to `path`, e.g. for hosts without access to the auth API.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DimoError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            DimoError::Config(format!("could not read JWKS '{}': {}", path.display(), e))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Fetches the JWKS document served at `base_url` + `path`, e.g. the auth host's `/keys`.
+    pub async fn fetch(client: &HttpClient, base_url: &str, path: &str) -> Result<Self, DimoError> {
+        let request_params = RequestParams {
+            method: Method::GET,
+            idempotent: true,
+            base_url: base_url.to_string(),
+            path: path.to_string(),
+            query_params: None,
+            body: None,
+            headers: None,
+        };
+
+        let response = client.make_request(request_params).await?;
+        let set = serde_json::from_value(response)?;
+        Ok(Self { set })
+    }
+
+    pub fn keys(&self) -> &[Jwk] {
+        &self.set.keys
+    }
+
+    /// The key with ID `kid`, or the only key in the set when the token names none.
+    pub(crate) fn key(&self, kid: Option<&str>) -> Option<&Jwk> {
+        match kid {
+            Some(kid) => self.set.find(kid),
+            None if self.set.keys.len() == 1 => self.set.keys.first(),
+            None => None,
+        }
+    }
+}
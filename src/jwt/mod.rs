@@ -0,0 +1,345 @@
+//! Decoding and verification of the developer and vehicle JWTs issued by DIMO.
+//!
+//! [`decode`] reads a token's claims without checking its signature, which is enough to
+//! look at its expiry or privileges. [`JwtVerifier`] additionally checks the signature
+//! against a [`Jwks`] and validates expiry, audience and issuer as configured.
+
+mod jwks;
+
+pub use crate::rest::tokenexchange::VehicleTokenClaims;
+pub use jsonwebtoken::Algorithm;
+pub use jwks::Jwks;
+
+use crate::utils::DimoError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The claims of the developer JWT returned by [`crate::rest::auth::AuthClient::submit_challenge`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeveloperClaims {
+    pub iss: String,
+    pub sub: String,
+    /// The client ID the token was issued to.
+    #[serde(deserialize_with = "one_or_many")]
+    pub aud: Vec<String>,
+    /// Expiry as seconds since the Unix epoch.
+    pub exp: u64,
+    pub iat: Option<u64>,
+    /// The address that signed the web3 challenge.
+    pub ethereum_address: Option<String>,
+    pub provider_id: Option<String>,
+}
+
+impl DeveloperClaims {
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.exp)
+    }
+}
+
+/// Which registered claims [`JwtVerifier`] checks besides the signature.
+#[derive(Debug, Clone)]
+pub struct JwtValidation {
+    /// Reject tokens whose `exp` has passed. Defaults to `true`.
+    pub validate_exp: bool,
+    /// Clock skew tolerated when checking `exp` and `nbf`. Defaults to 60 seconds.
+    pub leeway: Duration,
+    /// Accept only tokens issued to one of these audiences. `None` skips the check.
+    pub audience: Option<Vec<String>>,
+    /// Accept only tokens from one of these issuers. `None` skips the check.
+    pub issuer: Option<Vec<String>>,
+    /// Signature algorithms accepted. Defaults to `RS256`, which DIMO signs its tokens with.
+    pub algorithms: Vec<Algorithm>,
+}
+
+impl Default for JwtValidation {
+    fn default() -> Self {
+        Self {
+            validate_exp: true,
+            leeway: Duration::from_secs(60),
+            audience: None,
+            issuer: None,
+            algorithms: vec![Algorithm::RS256],
+        }
+    }
+}
+
+/// Verifies JWT signatures against a [`Jwks`] and decodes their claims.
+#[derive(Debug, Clone)]
+pub struct JwtVerifier {
+    jwks: Jwks,
+    validation: JwtValidation,
+}
+
+impl JwtVerifier {
+    pub fn new(jwks: Jwks) -> Self {
+        Self {
+            jwks,
+            validation: JwtValidation::default(),
+        }
+    }
+
+    pub fn with_validation(mut self, validation: JwtValidation) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Checks the signature and configured claims of `token` and returns its claims.
+    ///
+    /// The algorithm is taken from the key rather than from the token header, so a token
+    /// that claims another algorithm than its key's, or one outside
+    /// [`JwtValidation::algorithms`], is rejected.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Result<T, DimoError> {
+        let header = jsonwebtoken::decode_header(token)?;
+        let jwk = self.jwks.key(header.kid.as_deref()).ok_or_else(|| {
+            DimoError::InvalidToken(match header.kid {
+                Some(kid) => format!("no key with id '{}' in JWKS", kid),
+                None => "token names no key id and JWKS has several keys".to_string(),
+            })
+        })?;
+        let key = DecodingKey::from_jwk(jwk)?;
+
+        let alg = match jwk.common.key_algorithm {
+            Some(key_alg) => key_alg.to_string().parse().map_err(|_| {
+                DimoError::InvalidToken(format!("unsupported JWKS key algorithm {}", key_alg))
+            })?,
+            None => header.alg,
+        };
+        if header.alg != alg {
+            return Err(DimoError::InvalidToken(format!(
+                "token is signed with {:?} but its key is for {:?}",
+                header.alg, alg
+            )));
+        }
+        if !self.validation.algorithms.contains(&alg) {
+            return Err(DimoError::InvalidToken(format!(
+                "algorithm {:?} is not allowed",
+                alg
+            )));
+        }
+
+        let mut validation = Validation::new(alg);
+        validation.validate_exp = self.validation.validate_exp;
+        validation.leeway = self.validation.leeway.as_secs();
+        if !self.validation.validate_exp {
+            validation.required_spec_claims.clear();
+        }
+        match self.validation.audience {
+            Some(ref audience) => validation.set_audience(audience),
+            None => validation.validate_aud = false,
+        }
+        if let Some(ref issuer) = self.validation.issuer {
+            validation.set_issuer(issuer);
+        }
+
+        Ok(jsonwebtoken::decode(token, &key, &validation)?.claims)
+    }
+
+    pub fn verify_developer(&self, token: &str) -> Result<DeveloperClaims, DimoError> {
+        self.verify(token)
+    }
+
+    pub fn verify_vehicle(&self, token: &str) -> Result<VehicleTokenClaims, DimoError> {
+        self.verify(token)
+    }
+}
+
+/// Decodes the claims of `token` without verifying its signature or expiry.
+pub fn decode<T: DeserializeOwned>(token: &str) -> Result<T, DimoError> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| DimoError::InvalidToken("JWT has no payload".to_string()))?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| DimoError::InvalidToken(format!("JWT payload: {}", e)))?;
+    serde_json::from_slice(&payload)
+        .map_err(|e| DimoError::InvalidToken(format!("JWT claims: {}", e)))
+}
+
+pub fn decode_developer(token: &str) -> Result<DeveloperClaims, DimoError> {
+    decode(token)
+}
+
+pub fn decode_vehicle(token: &str) -> Result<VehicleTokenClaims, DimoError> {
+    decode(token)
+}
+
+/// `aud` may be a single string or an array of strings.
+pub(crate) fn one_or_many<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(value)) => vec![value],
+        Some(OneOrMany::Many(values)) => values,
+        None => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    const SECRET: &[u8] = b"a shared secret for the hmac tests";
+
+    fn jwks(alg: &str) -> Jwks {
+        Jwks::from_json(
+            &json!({ "keys": [{
+                "kty": "oct",
+                "kid": "k1",
+                "alg": alg,
+                "k": URL_SAFE_NO_PAD.encode(SECRET),
+            }] })
+            .to_string(),
+        )
+        .unwrap()
+    }
+
+    fn claims() -> serde_json::Value {
+        json!({
+            "iss": "https://auth.dimo.zone",
+            "sub": "subject",
+            "aud": "0xclient",
+            "exp": 4102444800u64,
+        })
+    }
+
+    fn token(alg: Algorithm) -> String {
+        signed(alg, &claims())
+    }
+
+    fn signed(alg: Algorithm, claims: &serde_json::Value) -> String {
+        let mut header = Header::new(alg);
+        header.kid = Some("k1".to_string());
+        jsonwebtoken::encode(&header, claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    }
+
+    fn allowing(algorithms: Vec<Algorithm>) -> JwtValidation {
+        JwtValidation {
+            algorithms,
+            ..JwtValidation::default()
+        }
+    }
+
+    fn hs256(validation: JwtValidation) -> JwtVerifier {
+        JwtVerifier::new(jwks("HS256")).with_validation(JwtValidation {
+            algorithms: vec![Algorithm::HS256],
+            ..validation
+        })
+    }
+
+    #[test]
+    fn accepts_only_rs256_by_default() {
+        let verifier = JwtVerifier::new(jwks("HS256"));
+        let err = verifier
+            .verify_developer(&token(Algorithm::HS256))
+            .unwrap_err();
+        assert!(err.to_string().contains("HS256 is not allowed"));
+
+        let verifier = verifier.with_validation(allowing(vec![Algorithm::HS256]));
+        let claims = verifier.verify_developer(&token(Algorithm::HS256)).unwrap();
+        assert_eq!(claims.aud, ["0xclient"]);
+    }
+
+    #[test]
+    fn rejects_tokens_claiming_another_algorithm_than_their_key() {
+        let verifier = JwtVerifier::new(jwks("HS384"))
+            .with_validation(allowing(vec![Algorithm::HS256, Algorithm::HS384]));
+
+        let err = verifier
+            .verify_developer(&token(Algorithm::HS256))
+            .unwrap_err();
+        assert!(err.to_string().contains("its key is for HS384"));
+        verifier.verify_developer(&token(Algorithm::HS384)).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_tampered_signature() {
+        let verifier =
+            JwtVerifier::new(jwks("HS256")).with_validation(allowing(vec![Algorithm::HS256]));
+        let mut tampered = token(Algorithm::HS256);
+        tampered.push('A');
+
+        assert!(matches!(
+            verifier.verify_developer(&tampered),
+            Err(DimoError::InvalidToken(_))
+        ));
+    }
+
+    #[test]
+    fn checks_the_audience_when_configured() {
+        let token = token(Algorithm::HS256);
+        hs256(JwtValidation::default())
+            .verify_developer(&token)
+            .unwrap();
+
+        let expecting = |audience: &str| {
+            hs256(JwtValidation {
+                audience: Some(vec![audience.to_string()]),
+                ..JwtValidation::default()
+            })
+        };
+        expecting("0xclient").verify_developer(&token).unwrap();
+        assert!(matches!(
+            expecting("0xother").verify_developer(&token),
+            Err(DimoError::InvalidToken(_))
+        ));
+    }
+
+    #[test]
+    fn checks_the_issuer_when_configured() {
+        let token = token(Algorithm::HS256);
+        let expecting = |issuer: &str| {
+            hs256(JwtValidation {
+                issuer: Some(vec![issuer.to_string()]),
+                ..JwtValidation::default()
+            })
+        };
+        expecting("https://auth.dimo.zone")
+            .verify_developer(&token)
+            .unwrap();
+        assert!(matches!(
+            expecting("https://auth.example.com").verify_developer(&token),
+            Err(DimoError::InvalidToken(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_expired_tokens_unless_told_not_to() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut claims = claims();
+        claims["exp"] = json!(now - 3600);
+        let expired = signed(Algorithm::HS256, &claims);
+
+        assert!(matches!(
+            hs256(JwtValidation::default()).verify_developer(&expired),
+            Err(DimoError::InvalidToken(_))
+        ));
+        // Within the leeway the token still passes.
+        let lenient = hs256(JwtValidation {
+            leeway: Duration::from_secs(7200),
+            ..JwtValidation::default()
+        });
+        lenient.verify_developer(&expired).unwrap();
+        let unchecked = hs256(JwtValidation {
+            validate_exp: false,
+            ..JwtValidation::default()
+        });
+        unchecked.verify_developer(&expired).unwrap();
+    }
+}
@@ -1,31 +1,56 @@
+mod builder;
+pub mod chain;
 pub mod environments;
 pub mod graphql;
+pub mod jwt;
 pub mod rest;
+pub mod signer;
+pub mod siwe;
 pub mod utils;
 
+pub use builder::DimoBuilder;
+pub use environments::dimo_environment::Routes;
+pub use rest::tokenexchange::Privilege;
 pub use serde_json::Value;
-pub use utils::credentials::get_credentials;
-use environments::dimo_environment;
+pub use signer::{LocalSigner, RemoteSigner, Signature, Signer};
+pub use utils::credentials::{
+    get_credentials, Credentials, CredentialsChain, CredentialsProvider, DotenvCredentials,
+    EnvCredentials, ProfileCredentials,
+};
+pub use utils::rate_limit::{RateLimit, RateLimits};
+pub use utils::request::{Attempted, ClientConfig, HttpClient};
+pub use utils::retry::RetryPolicy;
+pub use utils::transport::{HttpRequest, HttpResponse, HttpTransport, MockTransport};
+pub use utils::{AuthStep, DimoError};
+use chain::Chain;
 use graphql::{Identity, Telemetry};
 use rest::{
     attestation::AttestationClient,
-    auth::{AccessToken, AuthClient},
+    auth::{AccessToken, AuthClient, TokenManager},
     devicedefinitions::DeviceDefinitions,
     devices::Devices,
-    tokenexchange::TokenExchange,
+    tokenexchange::{TokenExchange, VehicleTokenStore},
     trips::Trips,
     valuations::Valuations,
 };
+use std::sync::Arc;
 
-#[derive(Clone, Copy)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
 pub enum Environment {
     Production,
     Dev,
+    /// Self-hosted or mock APIs, e.g. loaded with [`Routes::from_toml_file`] or
+    /// [`Routes::from_env`]. Uses the production contract addresses unless
+    /// [`DimoBuilder::constants`] sets others.
+    Custom(Routes),
 }
 
 pub struct DIMO {
     pub attestation: AttestationClient,
     pub auth: AuthClient,
+    /// JSON-RPC access to the vehicle and DLx contracts.
+    pub chain: Chain,
     pub devicedefinitions: DeviceDefinitions,
     pub devices: Devices,
     pub tokenexchange: TokenExchange,
@@ -33,54 +58,93 @@ pub struct DIMO {
     pub valuations: Valuations,
     pub identity: Identity,
     pub telemetry: Telemetry,
+    /// Source of the developer JWT used by every developer-scoped request.
+    pub tokens: Arc<TokenManager>,
+    /// Cache of vehicle JWTs used by every vehicle-scoped request.
+    pub vehicle_tokens: Arc<VehicleTokenStore>,
 }
 
 impl DIMO {
+    /// A [`DimoBuilder`] to configure the environment, credentials, signer, HTTP client
+    /// and token cache.
+    pub fn builder() -> DimoBuilder {
+        DimoBuilder::new()
+    }
+
+    /// Creates a `DIMO` instance for `env` with the default credentials chain, which is
+    /// read on the first developer-scoped request. Use [`DIMO::builder`] to load and check
+    /// the credentials up front.
+    ///
+    /// # Panics
+    ///
+    /// If `env` is [`Environment::Custom`] with a route that is not an http(s) URL. Use
+    /// [`DIMO::custom`] to get that as an error instead.
     pub fn new(env: Environment) -> Self {
-        let routes = match env {
-            Environment::Production => dimo_environment::PRODUCTION.routes,
-            Environment::Dev => dimo_environment::DEV.routes,
-        };
-        let constants = match env {
-            Environment::Production => dimo_environment::PRODUCTION.constants,
-            Environment::Dev => dimo_environment::DEV.constants,
-        };
+        Self::build_infallible(Self::builder().environment(env))
+    }
+
+    /// Creates a `DIMO` instance for self-hosted or mock APIs, failing when a route is not
+    /// an http(s) URL.
+    pub fn custom(routes: Routes) -> Result<Self, DimoError> {
+        Self::builder()
+            .environment(Environment::Custom(routes))
+            .lazy_credentials()
+            .build()
+    }
 
-        Self {
-            attestation: AttestationClient::new(routes.attestation.to_string()),
-            auth: AuthClient::new(routes.auth.to_string()),
-            devicedefinitions: DeviceDefinitions::new(routes.device_definitions),
-            devices: Devices::new(routes.devices),
-            tokenexchange: TokenExchange::new(routes.token_exchange, constants.nft_address),
-            trips: Trips::new(routes.trips),
-            valuations: Valuations::new(routes.valuations),
-            identity: Identity::new(routes.identity),
-            telemetry: Telemetry::new(routes.telemetry),
-        }
+    /// Creates a `DIMO` instance whose sub-clients share one HTTP client built from `config`.
+    pub fn with_config(env: Environment, config: ClientConfig) -> Result<Self, DimoError> {
+        Self::builder().environment(env).config(config).build()
     }
 
-    pub async fn get_token(&mut self) -> Result<AccessToken, Box<dyn std::error::Error>> {
-        let creds = get_credentials()?;
+    /// Creates a `DIMO` instance whose sub-clients send every request through `transport`,
+    /// e.g. a [`MockTransport`] in tests. Panics like [`DIMO::new`].
+    pub fn with_transport(env: Environment, transport: Arc<dyn HttpTransport>) -> Self {
+        Self::build_infallible(Self::builder().environment(env).transport(transport))
+    }
+
+    /// Creates a `DIMO` instance that reads its credentials from `credentials` instead of
+    /// the default [`CredentialsChain::default_chain`], on the first developer-scoped
+    /// request. Panics like [`DIMO::new`].
+    pub fn with_credentials<P: CredentialsProvider + 'static>(
+        env: Environment,
+        credentials: P,
+    ) -> Self {
+        Self::build_infallible(Self::builder().environment(env).credentials(credentials))
+    }
 
-        let challenge = self
-            .auth
-            .generate_challenge(&creds.client_id, &creds.domain)
-            .await
-            .expect("error generating challenge");
+    /// Creates a `DIMO` instance from a preconfigured [`HttpClient`]. Panics like
+    /// [`DIMO::new`].
+    pub fn with_client(env: Environment, client: HttpClient) -> Self {
+        Self::build_infallible(Self::builder().environment(env).http_client(client))
+    }
 
-        let state = challenge.state;
-        let challenge = challenge.challenge;
-        let signature = self
-            .auth
-            .sign_challenge(&challenge, &creds.private_key)
-            .expect("error signing challenge");
+    /// Builds without loading the credentials, which leaves only invalid custom routes and
+    /// a TLS backend that fails to initialize, as in `reqwest::Client::new`, to fail on.
+    fn build_infallible(builder: DimoBuilder) -> Self {
+        builder
+            .lazy_credentials()
+            .build()
+            .unwrap_or_else(|err| panic!("cannot create DIMO client: {}", err))
+    }
 
-        let token = self
-            .auth
-            .submit_challenge(&creds.client_id, &creds.domain, &state, &signature)
-            .await
-            .expect("error submitting challenge");
+    /// Runs the web3 challenge flow and caches the resulting developer JWT in
+    /// [`DIMO::tokens`]. The challenge is signed by the configured signer, or by a
+    /// [`LocalSigner`] built from the credentials' private key.
+    ///
+    /// Developer-scoped requests fetch and refresh the token on their own, so calling this
+    /// is only needed to read the token or to force a refresh.
+    pub async fn get_token(&mut self) -> Result<AccessToken, DimoError> {
+        self.tokens.refresh().await
+    }
 
-        Ok(token)
+    /// Like [`DIMO::get_token`], but signs the challenge with `signer`, which is also used
+    /// for later automatic refreshes.
+    pub async fn get_token_with_signer<S: Signer + 'static>(
+        &mut self,
+        signer: S,
+    ) -> Result<AccessToken, DimoError> {
+        self.tokens.set_signer(Arc::new(signer));
+        self.tokens.refresh().await
     }
 }
@@ -1,53 +1,71 @@
-use crate::utils::request::{make_auth_request, AuthRequestParams};
+use super::models::{PomVc, VinVc};
+use crate::rest::tokenexchange::Privilege;
+use crate::utils::request::{AuthRequestParams, HttpClient, TokenType};
+use crate::utils::DimoError;
 use reqwest::Method;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::error::Error;
+
+/// Privileges needed to create a VIN verifiable credential.
+const VIN_VC_PRIVILEGES: &[Privilege] = &[Privilege::VinCredential];
+/// Privileges needed to create a proof-of-movement verifiable credential.
+const POM_VC_PRIVILEGES: &[Privilege] = &[Privilege::AllTimeLocation];
 
 pub struct AttestationClient {
     base_url: String,
+    client: HttpClient,
 }
 
 impl AttestationClient {
-    pub fn new(base_url: String) -> Self {
-        Self { base_url }
+    pub fn new(base_url: String, client: HttpClient) -> Self {
+        Self { base_url, client }
+    }
+
+    pub async fn create_vin_vc(&self, token_id: &str, force: &str) -> Result<VinVc, DimoError> {
+        let response = self.create_vin_vc_raw(token_id, force).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn create_vin_vc(
-        &self,
-        token_id: &str,
-        force: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    /// Like [`AttestationClient::create_vin_vc`], but returns the response as JSON.
+    pub async fn create_vin_vc_raw(&self, token_id: &str, force: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/vc/vin/{}", token_id);
         let mut query_params: HashMap<String, String> = HashMap::new();
         query_params.insert("force".to_string(), force.to_string());
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.to_string(),
             path,
             query_params: Some(query_params),
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, VIN_VC_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn create_pom_vc(&self, token_id: &str) -> Result<PomVc, DimoError> {
+        let response = self.create_pom_vc_raw(token_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn create_pom_vc(&self, token_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`AttestationClient::create_pom_vc`], but returns the response as JSON.
+    pub async fn create_pom_vc_raw(&self, token_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/vc/pom/{}", token_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.to_string(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, POM_VC_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 }
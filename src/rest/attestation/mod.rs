@@ -1,2 +1,5 @@
+#[allow(clippy::module_inception)]
 pub mod attestation;
+pub mod models;
 pub use attestation::AttestationClient;
+pub use models::{PomVc, VinVc};
@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+/// The result of [`super::AttestationClient::create_vin_vc`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VinVc {
+    /// Where the verifiable credential can be fetched from.
+    pub vc_url: Option<String>,
+    /// The Telemetry API query that returns the credential.
+    pub vc_query: Option<String>,
+    pub message: Option<String>,
+}
+
+/// The result of [`super::AttestationClient::create_pom_vc`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct PomVc {
+    pub message: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::fixture;
+
+    #[test]
+    fn parses_vc_responses() {
+        let vin: VinVc = serde_json::from_value(fixture("attestation/vin_vc.json")).unwrap();
+        assert!(vin
+            .vc_query
+            .unwrap()
+            .contains("vinVCLatest(tokenId: 18934)"));
+        assert!(vin.vc_url.is_some());
+
+        let pom: PomVc = serde_json::from_value(fixture("attestation/pom_vc.json")).unwrap();
+        assert_eq!(pom.message.as_deref(), Some("VC generated successfully."));
+    }
+}
@@ -1,15 +1,17 @@
-use crate::utils::request::{make_request, RequestParams};
-use hex;
+use crate::jwt::{self, DeveloperClaims, Jwks};
+use crate::signer::{hash_message, verify_message, LocalSigner, Signature, Signer};
+use crate::siwe::SiweMessage;
+use crate::utils::request::{HttpClient, RequestParams};
+use crate::utils::{AuthStep, DimoError};
 use reqwest::Method;
-use secp256k1::{Message, Secp256k1, SecretKey};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
-use std::error::Error;
 
+#[derive(Clone)]
 pub struct AuthClient {
     base_url: String,
+    client: HttpClient,
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,7 +20,14 @@ pub struct ChallengeResponse {
     pub state: String,
 }
 
-#[derive(Deserialize, Debug)]
+impl ChallengeResponse {
+    /// Parses the challenge as the Sign-In With Ethereum message it is.
+    pub fn siwe_message(&self) -> Result<SiweMessage, DimoError> {
+        SiweMessage::parse(&self.challenge)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AccessToken {
     access_token: String,
     id_token: String,
@@ -26,25 +35,58 @@ pub struct AccessToken {
     expires_in: i32,
 }
 
+impl AccessToken {
+    /// The developer JWT, sent as the bearer token of developer-scoped requests.
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn id_token(&self) -> &str {
+        &self.id_token
+    }
+
+    pub fn token_type(&self) -> &str {
+        &self.token_type
+    }
+
+    /// Lifetime of the token in seconds, counted from when it was issued.
+    pub fn expires_in(&self) -> i32 {
+        self.expires_in
+    }
+
+    /// Decodes the claims of the developer JWT without verifying its signature.
+    pub fn claims(&self) -> Result<DeveloperClaims, DimoError> {
+        jwt::decode_developer(&self.access_token)
+    }
+}
+
 impl AuthClient {
-    pub fn new(base_url: String) -> Self {
-        Self { base_url }
+    pub fn new(base_url: String, client: HttpClient) -> Self {
+        Self { base_url, client }
+    }
+
+    pub(crate) fn base_url(&self) -> &str {
+        &self.base_url
     }
 
+    /// Requests a challenge for `address`, the EIP-55 address of a signer registered on
+    /// the developer license `client_id`.
     pub async fn generate_challenge(
         &self,
         client_id: &str,
         domain: &str,
-    ) -> Result<ChallengeResponse, Box<dyn Error>> {
+        address: &str,
+    ) -> Result<ChallengeResponse, DimoError> {
         let mut query_params: HashMap<String, String> = HashMap::new();
         query_params.insert("client_id".to_string(), client_id.to_string());
         query_params.insert("domain".to_string(), domain.to_string());
         query_params.insert("scope".to_string(), "openid email".to_string());
         query_params.insert("response_type".to_string(), "code".to_string());
-        query_params.insert("address".to_string(), client_id.to_string());
+        query_params.insert("address".to_string(), address.to_string());
 
         let params = RequestParams {
             method: Method::POST,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path: "/auth/web3/generate_challenge".to_string(),
             query_params: Some(query_params),
@@ -52,33 +94,51 @@ impl AuthClient {
             headers: None,
         };
 
-        let response = make_request(params).await?;
-        let challenge_response: ChallengeResponse = serde_json::from_value(response)?;
+        let response = self
+            .client
+            .make_request(params)
+            .await
+            .map_err(|e| e.during(AuthStep::GenerateChallenge))?;
+        let challenge_response: ChallengeResponse = serde_json::from_value(response)
+            .map_err(|e| DimoError::from(e).during(AuthStep::GenerateChallenge))?;
         Ok(challenge_response)
     }
 
-    pub fn sign_challenge(
+    /// Signs `message` with a hex encoded private key. Returns the hex encoded signature
+    /// without the `0x` prefix, as expected by [`AuthClient::submit_challenge`].
+    pub fn sign_challenge(&self, message: &str, private_key: &str) -> Result<String, DimoError> {
+        let signer =
+            LocalSigner::from_hex(private_key).map_err(|e| e.during(AuthStep::SignChallenge))?;
+        let signature = signer.sign_hash(&hash_message(message.as_bytes()));
+        Ok(hex::encode(signature.to_bytes()))
+    }
+
+    /// The inverse of [`AuthClient::sign_challenge`]: checks that the hex encoded
+    /// `signature` of `message` was made by `address`.
+    pub fn verify_challenge(
+        &self,
+        message: &str,
+        signature: &str,
+        address: &str,
+    ) -> Result<(), DimoError> {
+        verify_message(
+            message.as_bytes(),
+            &Signature::from_hex(signature)?,
+            address,
+        )
+    }
+
+    /// Like [`AuthClient::sign_challenge`], but signs with any [`Signer`].
+    pub async fn sign_challenge_with(
         &self,
         message: &str,
-        private_key: &str,
-    ) -> Result<String, Box<dyn Error>> {
-        let secp = Secp256k1::new();
-        let private_key_bytes = hex::decode(private_key.strip_prefix("0x").unwrap_or(private_key))?;
-        let secret_key = SecretKey::from_slice(&private_key_bytes)?;
-
-        let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
-        let prefixed_message = [prefix.as_bytes(), message.as_bytes()].concat();
-        let digest = Keccak256::digest(&prefixed_message);
-        let message = Message::from_digest_slice(&digest)?;
-        let signature = secp.sign_ecdsa_recoverable(&message, &secret_key);
-
-        let (recovery_id, signature_bytes) = signature.serialize_compact();
-        let v = recovery_id.to_i32() as u8 + 27;
-        let mut signature_full = Vec::with_capacity(65);
-        signature_full.extend_from_slice(&signature_bytes);
-        signature_full.push(v);
-
-        Ok(hex::encode(signature_full))
+        signer: &dyn Signer,
+    ) -> Result<String, DimoError> {
+        let signature = signer
+            .sign_message(message.as_bytes())
+            .await
+            .map_err(|e| e.during(AuthStep::SignChallenge))?;
+        Ok(hex::encode(signature.to_bytes()))
     }
 
     pub async fn submit_challenge(
@@ -87,7 +147,7 @@ impl AuthClient {
         domain: &str,
         state: &str,
         signature: &str,
-    ) -> Result<AccessToken, Box<dyn Error>> {
+    ) -> Result<AccessToken, DimoError> {
         let mut body_params: HashMap<String, Value> = HashMap::new();
         body_params.insert(
             "client_id".to_string(),
@@ -101,7 +161,7 @@ impl AuthClient {
         body_params.insert("state".to_string(), Value::String(state.to_string()));
         body_params.insert(
             "signature".to_string(),
-            Value::String(format!("0x{}", signature.to_string())),
+            Value::String(format!("0x{}", signature)),
         );
 
         let mut headers: HashMap<String, String> = HashMap::new();
@@ -112,6 +172,7 @@ impl AuthClient {
 
         let params = RequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path: "/auth/web3/submit_challenge".to_string(),
             query_params: None,
@@ -119,10 +180,19 @@ impl AuthClient {
             headers: Some(headers),
         };
 
-        let response = make_request(params).await.expect("could not submit");
-        let token: AccessToken =
-            serde_json::from_value(response).expect("could not parse response token");
+        let response = self
+            .client
+            .make_request(params)
+            .await
+            .map_err(|e| e.during(AuthStep::SubmitChallenge))?;
+        let token: AccessToken = serde_json::from_value(response)
+            .map_err(|e| DimoError::from(e).during(AuthStep::ParseToken))?;
 
         Ok(token)
     }
+
+    /// Fetches the JWKS the auth host signs developer JWTs with.
+    pub async fn jwks(&self) -> Result<Jwks, DimoError> {
+        Jwks::fetch(&self.client, &self.base_url, "/keys").await
+    }
 }
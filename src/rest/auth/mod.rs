@@ -1,2 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod auth;
+pub mod tokencache;
+pub mod tokenmanager;
 pub use auth::{AccessToken, AuthClient};
+pub use tokencache::{CachedToken, MemoryTokenCache, TokenCache};
+pub use tokenmanager::TokenManager;
@@ -0,0 +1,59 @@
+use super::auth::AccessToken;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedToken {
+    pub token: AccessToken,
+    /// When the token stops being valid, computed from `expires_in` when it was fetched.
+    pub expires_at: SystemTime,
+}
+
+impl CachedToken {
+    pub(crate) fn is_fresh(&self, margin: Duration) -> bool {
+        SystemTime::now() + margin < self.expires_at
+    }
+}
+
+/// Where [`super::TokenManager`] keeps the developer JWT between requests.
+///
+/// Implement it to persist the token, e.g. in a file or a shared store, so that restarts
+/// or other processes reuse it instead of running the web3 challenge flow again. A cache
+/// holds the token of a single developer license.
+#[async_trait]
+pub trait TokenCache: Send + Sync {
+    async fn get(&self) -> Option<CachedToken>;
+
+    async fn put(&self, token: CachedToken);
+
+    async fn clear(&self);
+}
+
+/// Keeps the token in memory. The default cache.
+#[derive(Debug, Default)]
+pub struct MemoryTokenCache {
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl MemoryTokenCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenCache for MemoryTokenCache {
+    async fn get(&self) -> Option<CachedToken> {
+        self.token.lock().await.clone()
+    }
+
+    async fn put(&self, token: CachedToken) {
+        *self.token.lock().await = Some(token);
+    }
+
+    async fn clear(&self) {
+        *self.token.lock().await = None;
+    }
+}
@@ -0,0 +1,422 @@
+use super::auth::{AccessToken, AuthClient};
+use super::tokencache::{CachedToken, MemoryTokenCache, TokenCache};
+use crate::graphql::Identity;
+use crate::signer::{same_address, LocalSigner, Signer};
+use crate::siwe::SiweValidation;
+use crate::utils::credentials::{
+    Credentials, CredentialsChain, CredentialsError, CredentialsProvider,
+};
+use crate::utils::{AuthStep, DimoError};
+use std::sync::{Arc, Mutex as StdMutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+/// Caches the developer JWT and re-runs the web3 challenge flow when it is about to expire.
+///
+/// [`crate::DIMO`] owns one `TokenManager` and uses it as the bearer for every
+/// developer-scoped request. A `401` from such a request invalidates the cached token,
+/// so the next call fetches a fresh one. The token is kept in a [`TokenCache`], in memory
+/// unless another cache is configured.
+///
+/// The client id and domain come from a [`CredentialsProvider`], read once on the first
+/// challenge. The challenge is signed by the configured [`Signer`], or by a [`LocalSigner`]
+/// built from the provider's private key when there is none.
+pub struct TokenManager {
+    auth: AuthClient,
+    provider: Arc<dyn CredentialsProvider>,
+    credentials: StdMutex<Option<Credentials>>,
+    identity: Option<Identity>,
+    signer: RwLock<Option<Arc<dyn Signer>>>,
+    /// The last signer address confirmed to be registered on the developer license.
+    authorized: StdMutex<Option<String>>,
+    refresh_margin: Duration,
+    cache: Arc<dyn TokenCache>,
+    /// Held while the cache is read and refilled, so concurrent requests share one fetch.
+    fetching: Mutex<()>,
+}
+
+impl TokenManager {
+    pub fn new(auth: AuthClient) -> Self {
+        Self {
+            auth,
+            provider: Arc::new(CredentialsChain::default_chain()),
+            credentials: StdMutex::new(None),
+            identity: None,
+            signer: RwLock::new(None),
+            authorized: StdMutex::new(None),
+            refresh_margin: Duration::from_secs(60),
+            cache: Arc::new(MemoryTokenCache::new()),
+            fetching: Mutex::new(()),
+        }
+    }
+
+    /// Before the first challenge with a signer, checks through `identity` that the signer
+    /// is registered on the developer license, failing with
+    /// [`DimoError::UnauthorizedSigner`] otherwise.
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Reads the credentials from `provider` instead of the default
+    /// [`CredentialsChain::default_chain`].
+    pub fn with_credentials(mut self, provider: Arc<dyn CredentialsProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    pub fn with_signer(self, signer: Arc<dyn Signer>) -> Self {
+        self.set_signer(signer);
+        self
+    }
+
+    /// Signs future challenges with `signer`. The cached token, if any, is kept.
+    pub fn set_signer(&self, signer: Arc<dyn Signer>) {
+        *self.signer.write().unwrap() = Some(signer);
+    }
+
+    /// Keeps the token in `cache` instead of in memory.
+    pub fn with_cache(mut self, cache: Arc<dyn TokenCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Refresh the token this long before it expires. Defaults to 60 seconds.
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// Returns the cached developer JWT, running the challenge flow first if there is none
+    /// or it expires within the refresh margin.
+    pub async fn token(&self) -> Result<String, DimoError> {
+        let _fetching = self.fetching.lock().await;
+        if let Some(current) = self.cache.get().await {
+            if current.is_fresh(self.refresh_margin) {
+                return Ok(current.token.access_token().to_string());
+            }
+        }
+
+        let fresh = self.fetch().await?;
+        let token = fresh.token.access_token().to_string();
+        self.cache.put(fresh).await;
+        Ok(token)
+    }
+
+    /// Runs the challenge flow unconditionally and caches the result.
+    pub async fn refresh(&self) -> Result<AccessToken, DimoError> {
+        let _fetching = self.fetching.lock().await;
+        let fresh = self.fetch().await?;
+        let token = fresh.token.clone();
+        self.cache.put(fresh).await;
+        Ok(token)
+    }
+
+    /// The cached token and its expiry, if one has been fetched.
+    pub async fn current(&self) -> Option<CachedToken> {
+        self.cache.get().await
+    }
+
+    /// Drops the cached token if it is still `rejected`, e.g. after the API answered `401`.
+    pub async fn invalidate(&self, rejected: &str) {
+        let _fetching = self.fetching.lock().await;
+        if matches!(self.cache.get().await, Some(c) if c.token.access_token() == rejected) {
+            self.cache.clear().await;
+        }
+    }
+
+    /// The provider's credentials, loaded on first use and kept afterwards.
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let mut loaded = self.credentials.lock().unwrap();
+        if let Some(ref credentials) = *loaded {
+            return Ok(credentials.clone());
+        }
+        let credentials = self.provider.credentials()?;
+        *loaded = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    async fn fetch(&self) -> Result<CachedToken, DimoError> {
+        let creds = self.credentials()?;
+        let signer = match self.signer.read().unwrap().clone() {
+            Some(signer) => signer,
+            None => {
+                let private_key = creds
+                    .private_key
+                    .as_deref()
+                    .ok_or(CredentialsError::MissingFields(vec!["api_key"]))?;
+                let signer = LocalSigner::from_hex(private_key)
+                    .map_err(|e| e.during(AuthStep::SignChallenge))?;
+                Arc::new(signer)
+            }
+        };
+
+        let address = signer
+            .address()
+            .await
+            .map_err(|e| e.during(AuthStep::SignChallenge))?;
+        self.authorize(&creds.client_id, &address).await?;
+
+        let challenge = self
+            .auth
+            .generate_challenge(&creds.client_id, &creds.domain, &address)
+            .await?;
+        // Only sign a challenge for this signer, from the configured domain or auth host.
+        let validation = SiweValidation {
+            domain: Some(vec![creds.domain.clone(), self.auth.base_url().to_string()]),
+            address: Some(address.clone()),
+            ..Default::default()
+        };
+        challenge
+            .siwe_message()
+            .and_then(|message| message.validate(&validation))
+            .map_err(|e| e.during(AuthStep::VerifyChallenge))?;
+        let signature = self
+            .auth
+            .sign_challenge_with(&challenge.challenge, signer.as_ref())
+            .await?;
+        let token = self
+            .auth
+            .submit_challenge(
+                &creds.client_id,
+                &creds.domain,
+                &challenge.state,
+                &signature,
+            )
+            .await?;
+
+        let lifetime = Duration::from_secs(token.expires_in().max(0) as u64);
+        Ok(CachedToken {
+            expires_at: SystemTime::now() + lifetime,
+            token,
+        })
+    }
+
+    async fn authorize(&self, client_id: &str, address: &str) -> Result<(), DimoError> {
+        let identity = match self.identity {
+            Some(ref identity) => identity,
+            None => return Ok(()),
+        };
+        if matches!(*self.authorized.lock().unwrap(), Some(ref a) if a == address) {
+            return Ok(());
+        }
+
+        let signers = identity
+            .developer_license_signers(client_id)
+            .await
+            .map_err(|e| e.during(AuthStep::GenerateChallenge))?;
+        if !signers.iter().any(|signer| same_address(signer, address)) {
+            return Err(DimoError::UnauthorizedSigner {
+                client_id: client_id.to_string(),
+                address: address.to_string(),
+            });
+        }
+
+        *self.authorized.lock().unwrap() = Some(address.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::Signature;
+    use crate::utils::request::HttpClient;
+    use crate::utils::retry::RetryPolicy;
+    use crate::utils::testing::{self, ok, CLIENT_ID, DOMAIN, PRIVATE_KEY};
+    use crate::utils::transport::{HttpResponse, MockTransport};
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+
+    fn manager(transport: &Arc<MockTransport>, private_key: &str) -> TokenManager {
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(RetryPolicy::none());
+        TokenManager::new(AuthClient::new(
+            "https://auth.dev.dimo.zone".to_string(),
+            client,
+        ))
+        .with_credentials(Arc::new(
+            Credentials::new(CLIENT_ID, DOMAIN).with_private_key(private_key),
+        ))
+    }
+
+    fn failed_step(err: &DimoError) -> AuthStep {
+        match err {
+            DimoError::Auth { step, .. } => *step,
+            err => panic!("expected an auth error, got {:?}", err),
+        }
+    }
+
+    fn on_challenge(transport: &MockTransport, challenge: &str) {
+        transport.on(
+            Method::POST,
+            "/auth/web3/generate_challenge",
+            ok(json!({ "challenge": challenge, "state": "state" })),
+        );
+    }
+
+    #[tokio::test]
+    async fn wraps_a_failing_challenge_request() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Method::POST,
+            "/auth/web3/generate_challenge",
+            HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR, ""),
+        );
+
+        let err = manager(&transport, PRIVATE_KEY).token().await.unwrap_err();
+        assert_eq!(failed_step(&err), AuthStep::GenerateChallenge);
+        assert_eq!(err.status(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn wraps_an_unusable_private_key() {
+        let transport = Arc::new(MockTransport::new());
+
+        let err = manager(&transport, "not a key").token().await.unwrap_err();
+        assert_eq!(failed_step(&err), AuthStep::SignChallenge);
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn wraps_a_rejected_signature() {
+        let transport = Arc::new(MockTransport::new());
+        on_challenge(&transport, &testing::challenge());
+        transport.on(
+            Method::POST,
+            "/auth/web3/submit_challenge",
+            HttpResponse::new(StatusCode::BAD_REQUEST, r#"{"error":"invalid signature"}"#),
+        );
+
+        let err = manager(&transport, PRIVATE_KEY).token().await.unwrap_err();
+        assert_eq!(failed_step(&err), AuthStep::SubmitChallenge);
+        assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[tokio::test]
+    async fn wraps_an_unexpected_token_response() {
+        let transport = Arc::new(MockTransport::new());
+        on_challenge(&transport, &testing::challenge());
+        transport.on(
+            Method::POST,
+            "/auth/web3/submit_challenge",
+            ok(json!({ "error": "no token" })),
+        );
+
+        let err = manager(&transport, PRIVATE_KEY).token().await.unwrap_err();
+        assert_eq!(failed_step(&err), AuthStep::ParseToken);
+        assert!(matches!(err.root(), DimoError::Json(_)));
+    }
+
+    fn signer_page(addresses: &[&str], end_cursor: Option<&str>) -> HttpResponse {
+        let nodes: Vec<_> = addresses.iter().map(|a| json!({ "address": a })).collect();
+        ok(json!({ "data": { "developerLicense": { "signers": {
+            "nodes": nodes,
+            "pageInfo": { "hasNextPage": end_cursor.is_some(), "endCursor": end_cursor },
+        } } } }))
+    }
+
+    fn checked(transport: &Arc<MockTransport>) -> TokenManager {
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(RetryPolicy::none());
+        manager(transport, PRIVATE_KEY).with_identity(Identity::new(
+            "https://identity-api.dev.dimo.zone/query",
+            client,
+        ))
+    }
+
+    fn submitted(transport: &MockTransport) -> usize {
+        transport
+            .requests()
+            .iter()
+            .filter(|r| r.url.contains("/auth/web3/"))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn finds_the_signer_on_a_later_page() {
+        let transport = Arc::new(MockTransport::new());
+        on_challenge(&transport, &testing::challenge());
+        transport.on(
+            Method::POST,
+            "/auth/web3/submit_challenge",
+            ok(json!({
+                "access_token": "DEV",
+                "id_token": "id",
+                "token_type": "bearer",
+                "expires_in": 3600,
+            })),
+        );
+        transport
+            .push(signer_page(&["0x01"], Some("c1")))
+            .push(signer_page(
+                &[&testing::SIGNER_ADDRESS.to_lowercase()],
+                None,
+            ));
+        let tokens = checked(&transport);
+
+        assert_eq!(tokens.token().await.unwrap(), "DEV");
+        assert_eq!(submitted(&transport), 2);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_signer_missing_from_every_page() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(signer_page(&["0x01", "0x02"], Some("c1")))
+            .push(signer_page(&["0x03"], None));
+        let tokens = checked(&transport);
+
+        match tokens.token().await.unwrap_err() {
+            DimoError::UnauthorizedSigner { client_id, address } => {
+                assert_eq!(client_id, CLIENT_ID);
+                assert_eq!(address, testing::SIGNER_ADDRESS);
+            }
+            err => panic!("expected an unauthorized signer, got {:?}", err),
+        }
+        assert_eq!(transport.requests().len(), 2);
+        assert_eq!(submitted(&transport), 0);
+    }
+
+    /// A signer that fails the test if it is asked to sign.
+    struct MustNotSign;
+
+    #[async_trait::async_trait]
+    impl Signer for MustNotSign {
+        async fn address(&self) -> Result<String, DimoError> {
+            Ok(testing::SIGNER_ADDRESS.to_string())
+        }
+
+        async fn sign_message(&self, _message: &[u8]) -> Result<Signature, DimoError> {
+            panic!("signed a challenge that should have been rejected")
+        }
+    }
+
+    async fn refused(challenge: &str) {
+        let transport = Arc::new(MockTransport::new());
+        on_challenge(&transport, challenge);
+        let tokens = manager(&transport, PRIVATE_KEY).with_signer(Arc::new(MustNotSign));
+
+        let err = tokens.token().await.unwrap_err();
+        assert_eq!(failed_step(&err), AuthStep::VerifyChallenge);
+        assert!(matches!(err.root(), DimoError::Siwe(_)));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].url.contains("/auth/web3/generate_challenge"));
+    }
+
+    #[tokio::test]
+    async fn refuses_a_challenge_for_another_domain() {
+        refused(&testing::challenge().replace("localhost:8080 wants", "evil.example wants")).await;
+    }
+
+    #[tokio::test]
+    async fn refuses_an_expired_challenge() {
+        refused(&format!(
+            "{}\nExpiration Time: 2024-01-02T04:04:05Z",
+            testing::challenge()
+        ))
+        .await;
+    }
+}
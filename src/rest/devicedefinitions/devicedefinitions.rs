@@ -1,57 +1,56 @@
-use crate::utils::request::{make_request, RequestParams, make_auth_request, AuthRequestParams};
+use super::models::{DecodeVinRequest, DecodeVinResponse, DeviceDefinitionSearch};
+use crate::utils::request::{to_body, AuthRequestParams, HttpClient, RequestParams, TokenType};
+use crate::utils::DimoError;
 use reqwest::Method;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::error::Error;
 
 pub struct DeviceDefinitions {
     base_url: String,
+    client: HttpClient,
 }
 
 impl DeviceDefinitions {
-    pub fn new(base_url: &str) -> Self {
+    pub fn new(base_url: &str, client: HttpClient) -> Self {
         Self {
             base_url: base_url.to_string(),
+            client,
         }
     }
 
-    /// Decodes a VIN (Vehicle Identification Number) and retrieves detailed information.
+    /// Decodes a VIN (Vehicle Identification Number) into a device definition.
     /// Requires a developer jwt
     ///
-    /// ### Body Parameters
-    /// The `body` HashMap must include:
-    /// - **`vin`** *(String, Required)*: Vehicle Identification Number.
-    /// - **`countryCode`** *(String, Required)*: 3-letter ISO 3166-1 alpha-3 country code, e.g. "USA".
-    ///
-    /// ### Authentication
-    /// Requires an access token to be passed (`access_token`) for authorization.
-    ///
     /// ### Example
     /// ```ignore
-    /// let mut body = HashMap::new();
-    /// body.insert("vin".to_string(), "1HGCM82633A123456".to_string());
-    /// body.insert("countryCode".to_string(), "US".to_string());
-    ///
-    /// let response = device_definitions.decode_vin(body, "access_token").await?;
-    /// println!("{:?}", response);
+    /// let request = DecodeVinRequest::new("1HGCM82633A123456", "USA");
+    /// let response = device_definitions.decode_vin(&request).await?;
+    /// println!("{}", response.device_definition_id);
     /// ```
     pub async fn decode_vin(
         &self,
-        body: HashMap<String, String>,
-    ) -> Result<Value, Box<dyn Error>> {
+        request: &DecodeVinRequest,
+    ) -> Result<DecodeVinResponse, DimoError> {
+        let response = self.decode_vin_raw(request).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`DeviceDefinitions::decode_vin`], but returns the response as JSON.
+    pub async fn decode_vin_raw(&self, request: &DecodeVinRequest) -> Result<Value, DimoError> {
         let path = "/device-definitions/decode-vin".to_string();
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
-            body: Some(body.into_iter().map(|(k, v)| (k, Value::String(v))).collect()),
+            body: Some(to_body(request)?),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     /// Searches device definitions with a GraphQL query and optional additional parameters.
@@ -73,13 +72,23 @@ impl DeviceDefinitions {
     /// additional_params.insert("year".to_string(), "2022".to_string());
     ///
     /// let response = device_definitions.search(query, Some(additional_params)).await?;
-    /// println!("{:?}", response);
+    /// println!("{:?}", response.device_definitions);
     /// ```
     pub async fn search(
         &self,
         query: &str,
         additional_params: Option<HashMap<String, String>>,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<DeviceDefinitionSearch, DimoError> {
+        let response = self.search_raw(query, additional_params).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`DeviceDefinitions::search`], but returns the response as JSON.
+    pub async fn search_raw(
+        &self,
+        query: &str,
+        additional_params: Option<HashMap<String, String>>,
+    ) -> Result<Value, DimoError> {
         let path = "/device-definitions/search".to_string();
         let mut query_params = HashMap::new();
         query_params.insert("query".to_string(), query.to_string());
@@ -90,6 +99,7 @@ impl DeviceDefinitions {
 
         let request_params = RequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: Some(query_params),
@@ -97,6 +107,6 @@ impl DeviceDefinitions {
             headers: None,
         };
 
-        make_request(request_params).await
+        self.client.make_request(request_params).await
     }
 }
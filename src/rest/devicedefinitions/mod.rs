@@ -1,2 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod devicedefinitions;
+pub mod models;
 pub use devicedefinitions::DeviceDefinitions;
+pub use models::{
+    DecodeVinRequest, DecodeVinResponse, DeviceDefinitionItem, DeviceDefinitionSearch, Pagination,
+};
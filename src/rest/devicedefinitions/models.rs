@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeVinRequest {
+    /// Vehicle Identification Number.
+    pub vin: String,
+    /// 3-letter ISO 3166-1 alpha-3 country code, e.g. "USA".
+    pub country_code: String,
+}
+
+impl DecodeVinRequest {
+    pub fn new(vin: &str, country_code: &str) -> Self {
+        Self {
+            vin: vin.to_string(),
+            country_code: country_code.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeVinResponse {
+    pub device_definition_id: String,
+    /// Set when decoding the VIN created a new device definition on chain.
+    pub new_transaction_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDefinitionSearch {
+    #[serde(default)]
+    pub device_definitions: Vec<DeviceDefinitionItem>,
+    pub pagination: Option<Pagination>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDefinitionItem {
+    pub id: String,
+    #[serde(alias = "legacy_id")]
+    pub legacy_id: Option<String>,
+    pub manufacturer: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<u32>,
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub page_size: u32,
+    #[serde(default)]
+    pub total_items: u32,
+    #[serde(default)]
+    pub total_pages: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::fixture;
+
+    #[test]
+    fn parses_decode_vin_responses() {
+        let response: DecodeVinResponse =
+            serde_json::from_value(fixture("devicedefinitions/decode_vin.json")).unwrap();
+        assert_eq!(response.device_definition_id, "ford_bronco_2022");
+        assert!(response.new_transaction_hash.is_some());
+    }
+
+    #[test]
+    fn parses_search_responses() {
+        let search: DeviceDefinitionSearch =
+            serde_json::from_value(fixture("devicedefinitions/search.json")).unwrap();
+        assert_eq!(search.device_definitions.len(), 2);
+        let bronco = &search.device_definitions[0];
+        assert_eq!(
+            bronco.legacy_id.as_deref(),
+            Some("22N2xaPOq2WW2gAHBHd0Ikn4Zob")
+        );
+        assert_eq!(bronco.year, Some(2022));
+        assert_eq!(search.device_definitions[1].image_url, None);
+        assert_eq!(search.pagination.unwrap().total_items, 2);
+    }
+}
@@ -1,17 +1,31 @@
-use crate::utils::request::{make_auth_request, make_request, AuthRequestParams, RequestParams};
+use super::models::{
+    AftermarketDeviceImage, AftermarketDeviceMetadata, ClaimRequest, ClaimSignatures,
+    ClaimingPayload, CommandResponse, CreateVehicleFromSmartcarRequest,
+    CreateVehicleFromVinRequest, CreateVehicleRequest, ErrorCodesQuery, ErrorCodesResponse,
+    MintBody, MintPayload, MintRequest, PairingPayload, PairingRequest, RegisterUserDeviceResponse,
+    SubmitErrorCodesRequest, UserDevice, UserDeviceEnvelope,
+};
+use crate::rest::tokenexchange::Privilege;
+use crate::signer::{Signer, TypedData};
+use crate::utils::request::{to_body, AuthRequestParams, HttpClient, RequestParams, TokenType};
+use crate::utils::transport::HttpResponse;
+use crate::utils::DimoError;
 use reqwest::Method;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::error::Error;
+
+/// Privileges needed to send commands to a vehicle.
+const COMMAND_PRIVILEGES: &[Privilege] = &[Privilege::Commands];
 
 pub struct Devices {
     base_url: String,
+    client: HttpClient,
 }
 
 impl Devices {
-    pub fn new(base_url: &str) -> Self {
+    pub fn new(base_url: &str, client: HttpClient) -> Self {
         Self {
             base_url: base_url.to_string(),
+            client,
         }
     }
 
@@ -19,29 +33,37 @@ impl Devices {
         &self,
         country_code: &str,
         device_definition_id: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<RegisterUserDeviceResponse, DimoError> {
+        let response = self
+            .create_vehicle_raw(country_code, device_definition_id)
+            .await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::create_vehicle`], but returns the response as JSON.
+    pub async fn create_vehicle_raw(
+        &self,
+        country_code: &str,
+        device_definition_id: &str,
+    ) -> Result<Value, DimoError> {
         let path = "/v1/user/devices".to_string();
-        let mut body = HashMap::new();
-        body.insert(
-            "countryCode".to_string(),
-            Value::String(country_code.to_string()),
-        );
-        body.insert(
-            "deviceDefinitionId".to_string(),
-            Value::String(device_definition_id.to_string()),
-        );
+        let body = to_body(&CreateVehicleRequest {
+            country_code: country_code.to_string(),
+            device_definition_id: device_definition_id.to_string(),
+        })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn create_vehicle_from_smartcar(
@@ -49,30 +71,40 @@ impl Devices {
         code: &str,
         country_code: &str,
         redirect_uri: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<UserDevice, DimoError> {
+        let response = self
+            .create_vehicle_from_smartcar_raw(code, country_code, redirect_uri)
+            .await?;
+        let envelope: UserDeviceEnvelope = serde_json::from_value(response)?;
+        Ok(envelope.user_device)
+    }
+
+    /// Like [`Devices::create_vehicle_from_smartcar`], but returns the response as JSON.
+    pub async fn create_vehicle_from_smartcar_raw(
+        &self,
+        code: &str,
+        country_code: &str,
+        redirect_uri: &str,
+    ) -> Result<Value, DimoError> {
         let path = "/v1/user/devices/fromsmartcar".to_string();
-        let mut body = HashMap::new();
-        body.insert("code".to_string(), Value::String(code.to_string()));
-        body.insert(
-            "countryCode".to_string(),
-            Value::String(country_code.to_string()),
-        );
-        body.insert(
-            "redirectURI".to_string(),
-            Value::String(redirect_uri.to_string()),
-        );
+        let body = to_body(&CreateVehicleFromSmartcarRequest {
+            code: code.to_string(),
+            country_code: country_code.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+        })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn create_vehicle_from_vin(
@@ -80,165 +112,363 @@ impl Devices {
         vin: &str,
         country_code: &str,
         can_protocol: Option<&str>,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<UserDevice, DimoError> {
+        let response = self
+            .create_vehicle_from_vin_raw(vin, country_code, can_protocol)
+            .await?;
+        let envelope: UserDeviceEnvelope = serde_json::from_value(response)?;
+        Ok(envelope.user_device)
+    }
+
+    /// Like [`Devices::create_vehicle_from_vin`], but returns the response as JSON.
+    pub async fn create_vehicle_from_vin_raw(
+        &self,
+        vin: &str,
+        country_code: &str,
+        can_protocol: Option<&str>,
+    ) -> Result<Value, DimoError> {
         let path = "/v1/user/devices/fromvin".to_string();
-        let mut body = HashMap::new();
-        body.insert("vin".to_string(), Value::String(vin.to_string()));
-        body.insert(
-            "countryCode".to_string(),
-            Value::String(country_code.to_string()),
-        );
-        if let Some(protocol) = can_protocol {
-            body.insert(
-                "canProtocol".to_string(),
-                Value::String(protocol.to_string()),
-            );
-        }
+        let body = to_body(&CreateVehicleFromVinRequest {
+            vin: vin.to_string(),
+            country_code: country_code.to_string(),
+            can_protocol: can_protocol.map(str::to_string),
+        })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn update_vehicle_vin(&self, user_device_id: &str) -> Result<(), DimoError> {
+        self.update_vehicle_vin_raw(user_device_id).await?;
+        Ok(())
     }
 
-    pub async fn update_vehicle_vin(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::update_vehicle_vin`], but returns the response as JSON.
+    pub async fn update_vehicle_vin_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/vin", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::PATCH,
+            idempotent: false,
+            base_url: self.base_url.clone(),
+            path,
+            query_params: None,
+            body: None,
+            headers: None,
+            token_type: TokenType::Developer,
+        };
+
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn delete_vehicle(&self, user_device_id: &str) -> Result<(), DimoError> {
+        self.delete_vehicle_raw(user_device_id).await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::delete_vehicle`], but returns the response as JSON.
+    pub async fn delete_vehicle_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
+        let path = format!("/v1/user/devices/{}", user_device_id);
+
+        let request_params = AuthRequestParams {
+            method: Method::DELETE,
+            idempotent: true,
+            base_url: self.base_url.clone(),
+            path,
+            query_params: None,
+            body: None,
+            headers: None,
+            token_type: TokenType::Developer,
+        };
+
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn delete_integration(
+        &self,
+        user_device_id: &str,
+        integration_id: &str,
+    ) -> Result<(), DimoError> {
+        self.delete_integration_raw(user_device_id, integration_id)
+            .await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::delete_integration`], but returns the response as JSON.
+    pub async fn delete_integration_raw(
+        &self,
+        user_device_id: &str,
+        integration_id: &str,
+    ) -> Result<Value, DimoError> {
+        let path = format!(
+            "/v1/user/devices/{}/integrations/{}",
+            user_device_id, integration_id
+        );
+
+        let request_params = AuthRequestParams {
+            method: Method::DELETE,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn get_claiming_payload(&self, serial: &str) -> Result<Value, Box<dyn Error>> {
+    pub async fn get_claiming_payload(&self, serial: &str) -> Result<ClaimingPayload, DimoError> {
+        let response = self.get_claiming_payload_raw(serial).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::get_claiming_payload`], but returns the response as JSON.
+    pub async fn get_claiming_payload_raw(&self, serial: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/aftermarket/device/by-serial/{}/commands/claim", serial);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn sign_claiming_payload(
         &self,
         serial: &str,
         claim_request: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<(), DimoError> {
+        self.sign_claiming_payload_raw(serial, claim_request)
+            .await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::sign_claiming_payload`], but returns the response as JSON.
+    pub async fn sign_claiming_payload_raw(
+        &self,
+        serial: &str,
+        claim_request: &str,
+    ) -> Result<Value, DimoError> {
         let path = format!("/v1/aftermarket/device/by-serial/{}/commands/claim", serial);
-        let mut body = HashMap::new();
-        body.insert(
-            "claimRequest".to_string(),
-            Value::String(claim_request.to_string()),
-        );
+        let body = to_body(&ClaimRequest {
+            claim_request: claim_request.to_string(),
+        })?;
+
+        let request_params = AuthRequestParams {
+            method: Method::POST,
+            idempotent: false,
+            base_url: self.base_url.clone(),
+            path,
+            query_params: None,
+            body: Some(body),
+            headers: None,
+            token_type: TokenType::Developer,
+        };
+
+        self.client.make_auth_request(request_params).await
+    }
+
+    /// Submits both signatures of the claiming payload of `serial`.
+    pub async fn submit_claim(
+        &self,
+        serial: &str,
+        signatures: &ClaimSignatures,
+    ) -> Result<(), DimoError> {
+        self.submit_claim_raw(serial, signatures).await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::submit_claim`], but returns the response as JSON.
+    pub async fn submit_claim_raw(
+        &self,
+        serial: &str,
+        signatures: &ClaimSignatures,
+    ) -> Result<Value, DimoError> {
+        let path = format!("/v1/aftermarket/device/by-serial/{}/commands/claim", serial);
+        let body = to_body(signatures)?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn get_minting_payload(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Fetches the claiming payload for `serial`, signs it with `signer` and submits it
+    /// together with `aftermarket_device_signature`.
+    ///
+    /// A claim needs the device's own signature of the same payload, which only its
+    /// firmware can produce; get it from the device before calling this.
+    pub async fn claim_aftermarket_device(
+        &self,
+        serial: &str,
+        aftermarket_device_signature: &str,
+        signer: &dyn Signer,
+    ) -> Result<(), DimoError> {
+        let payload = self.get_claiming_payload(serial).await?;
+        let signatures = ClaimSignatures {
+            user_signature: sign_payload(&payload, signer).await?,
+            aftermarket_device_signature: aftermarket_device_signature.to_string(),
+        };
+        self.submit_claim(serial, &signatures).await
+    }
+
+    pub async fn get_minting_payload(
+        &self,
+        user_device_id: &str,
+    ) -> Result<MintPayload, DimoError> {
+        let response = self.get_minting_payload_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::get_minting_payload`], but returns the response as JSON.
+    pub async fn get_minting_payload_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/commands/mint", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn sign_minting_payload(
         &self,
         user_device_id: &str,
-        mint_request: Value,
-    ) -> Result<Value, Box<dyn Error>> {
+        mint_request: &MintRequest,
+    ) -> Result<(), DimoError> {
+        self.sign_minting_payload_raw(user_device_id, mint_request)
+            .await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::sign_minting_payload`], but returns the response as JSON.
+    pub async fn sign_minting_payload_raw(
+        &self,
+        user_device_id: &str,
+        mint_request: &MintRequest,
+    ) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/commands/mint", user_device_id);
-        let mut body = HashMap::new();
-        body.insert("mintRequest".to_string(), mint_request);
+        let body = to_body(&MintBody { mint_request })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    /// Fetches the minting payload for `user_device_id`, signs it with `signer` and submits it.
+    pub async fn mint_vehicle(
+        &self,
+        user_device_id: &str,
+        signer: &dyn Signer,
+    ) -> Result<(), DimoError> {
+        let payload = self.get_minting_payload(user_device_id).await?;
+        let signature = sign_payload(&payload, signer).await?;
+        self.sign_minting_payload(user_device_id, &MintRequest::new(&signature))
+            .await
+    }
+
+    pub async fn opt_in_share_data(&self, user_device_id: &str) -> Result<(), DimoError> {
+        self.opt_in_share_data_raw(user_device_id).await?;
+        Ok(())
     }
 
-    pub async fn opt_in_share_data(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::opt_in_share_data`], but returns the response as JSON.
+    pub async fn opt_in_share_data_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/commands/opt-in", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn refresh_smartcar_data(
+    pub async fn refresh_smartcar_data(&self, user_device_id: &str) -> Result<(), DimoError> {
+        self.refresh_smartcar_data_raw(user_device_id).await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::refresh_smartcar_data`], but returns the response as JSON.
+    pub async fn refresh_smartcar_data_raw(
         &self,
         user_device_id: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/commands/refresh", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn get_pairing_payload(
+        &self,
+        user_device_id: &str,
+    ) -> Result<PairingPayload, DimoError> {
+        let response = self.get_pairing_payload_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn get_pairing_payload(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::get_pairing_payload`], but returns the response as JSON.
+    pub async fn get_pairing_payload_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!(
             "/v1/user/devices/{}/aftermarket/commands/pair",
             user_device_id
@@ -246,49 +476,80 @@ impl Devices {
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn sign_pairing_payload(
         &self,
         user_device_id: &str,
         user_signature: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<(), DimoError> {
+        self.sign_pairing_payload_raw(user_device_id, user_signature)
+            .await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::sign_pairing_payload`], but returns the response as JSON.
+    pub async fn sign_pairing_payload_raw(
+        &self,
+        user_device_id: &str,
+        user_signature: &str,
+    ) -> Result<Value, DimoError> {
         let path = format!(
             "/v1/user/devices/{}/aftermarket/commands/pair",
             user_device_id
         );
-        let mut body = HashMap::new();
-        body.insert(
-            "userSignature".to_string(),
-            Value::String(user_signature.to_string()),
-        );
+        let body = to_body(&PairingRequest {
+            user_signature: user_signature.to_string(),
+        })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    /// Fetches the pairing payload for `user_device_id`, signs it with `signer` and submits it.
+    pub async fn pair_aftermarket_device(
+        &self,
+        user_device_id: &str,
+        signer: &dyn Signer,
+    ) -> Result<(), DimoError> {
+        let payload = self.get_pairing_payload(user_device_id).await?;
+        let signature = sign_payload(&payload, signer).await?;
+        self.sign_pairing_payload(user_device_id, &signature).await
     }
 
     pub async fn get_unpairing_payload(
         &self,
         user_device_id: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<PairingPayload, DimoError> {
+        let response = self.get_unpairing_payload_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::get_unpairing_payload`], but returns the response as JSON.
+    pub async fn get_unpairing_payload_raw(
+        &self,
+        user_device_id: &str,
+    ) -> Result<Value, DimoError> {
         let path = format!(
             "/v1/user/devices/{}/aftermarket/commands/unpair",
             user_device_id
@@ -296,171 +557,264 @@ impl Devices {
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn sign_unpairing_payload(
         &self,
         user_device_id: &str,
         user_signature: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<(), DimoError> {
+        self.sign_unpairing_payload_raw(user_device_id, user_signature)
+            .await?;
+        Ok(())
+    }
+
+    /// Like [`Devices::sign_unpairing_payload`], but returns the response as JSON.
+    pub async fn sign_unpairing_payload_raw(
+        &self,
+        user_device_id: &str,
+        user_signature: &str,
+    ) -> Result<Value, DimoError> {
         let path = format!(
             "/v1/user/devices/{}/aftermarket/commands/unpair",
             user_device_id
         );
-        let mut body = HashMap::new();
-        body.insert(
-            "userSignature".to_string(),
-            Value::String(user_signature.to_string()),
-        );
+        let body = to_body(&PairingRequest {
+            user_signature: user_signature.to_string(),
+        })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn lock_doors(&self, token_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Fetches the unpairing payload for `user_device_id`, signs it with `signer` and submits it.
+    pub async fn unpair_aftermarket_device(
+        &self,
+        user_device_id: &str,
+        signer: &dyn Signer,
+    ) -> Result<(), DimoError> {
+        let payload = self.get_unpairing_payload(user_device_id).await?;
+        let signature = sign_payload(&payload, signer).await?;
+        self.sign_unpairing_payload(user_device_id, &signature)
+            .await
+    }
+
+    pub async fn lock_doors(&self, token_id: &str) -> Result<CommandResponse, DimoError> {
+        let response = self.lock_doors_raw(token_id).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::lock_doors`], but returns the response as JSON.
+    pub async fn lock_doors_raw(&self, token_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/vehicle/{}/commands/doors/lock", token_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, COMMAND_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn unlock_doors(&self, token_id: &str) -> Result<CommandResponse, DimoError> {
+        let response = self.unlock_doors_raw(token_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn unlock_doors(&self, token_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::unlock_doors`], but returns the response as JSON.
+    pub async fn unlock_doors_raw(&self, token_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/vehicle/{}/commands/doors/unlock", token_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, COMMAND_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn open_frunk(&self, token_id: &str) -> Result<Value, Box<dyn Error>> {
+    pub async fn open_frunk(&self, token_id: &str) -> Result<CommandResponse, DimoError> {
+        let response = self.open_frunk_raw(token_id).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::open_frunk`], but returns the response as JSON.
+    pub async fn open_frunk_raw(&self, token_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/vehicle/{}/commands/frunk/open", token_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, COMMAND_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn open_trunk(&self, token_id: &str) -> Result<CommandResponse, DimoError> {
+        let response = self.open_trunk_raw(token_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn open_trunk(&self, token_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::open_trunk`], but returns the response as JSON.
+    pub async fn open_trunk_raw(&self, token_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/vehicle/{}/commands/trunk/open", token_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, COMMAND_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn list_error_codes(
+        &self,
+        user_device_id: &str,
+    ) -> Result<ErrorCodesResponse, DimoError> {
+        let response = self.list_error_codes_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn list_error_codes(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::list_error_codes`], but returns the response as JSON.
+    pub async fn list_error_codes_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/error-codes", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
     pub async fn submit_error_codes(
         &self,
         user_device_id: &str,
         query_device_error_codes: bool,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<ErrorCodesQuery, DimoError> {
+        let response = self
+            .submit_error_codes_raw(user_device_id, query_device_error_codes)
+            .await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::submit_error_codes`], but returns the response as JSON.
+    pub async fn submit_error_codes_raw(
+        &self,
+        user_device_id: &str,
+        query_device_error_codes: bool,
+    ) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/error-codes", user_device_id);
-        let mut body = HashMap::new();
-        body.insert(
-            "queryDeviceErrorCodes".to_string(),
-            Value::Bool(query_device_error_codes),
-        );
+        let body = to_body(&SubmitErrorCodesRequest {
+            query_device_error_codes,
+        })?;
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn clear_error_codes(
+        &self,
+        user_device_id: &str,
+    ) -> Result<ErrorCodesQuery, DimoError> {
+        let response = self.clear_error_codes_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn clear_error_codes(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Devices::clear_error_codes`], but returns the response as JSON.
+    pub async fn clear_error_codes_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/user/devices/{}/error-codes/clear", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: false,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn get_aftermarket_device(&self, token_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// The NFT metadata of the aftermarket device `token_id`.
+    pub async fn get_aftermarket_device(
+        &self,
+        token_id: &str,
+    ) -> Result<AftermarketDeviceMetadata, DimoError> {
+        let response = self.get_aftermarket_device_raw(token_id).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::get_aftermarket_device`], but returns the response as JSON.
+    pub async fn get_aftermarket_device_raw(&self, token_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v1/aftermarket/device/{}", token_id);
 
         let request_params = RequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
@@ -468,17 +822,30 @@ impl Devices {
             headers: None,
         };
 
-        make_request(request_params).await
+        self.client.make_request(request_params).await
     }
 
+    /// The NFT image of the aftermarket device `token_id`.
     pub async fn get_aftermarket_device_image(
         &self,
         token_id: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<AftermarketDeviceImage, DimoError> {
+        let response = self.get_aftermarket_device_image_raw(token_id).await?;
+        Ok(response.into())
+    }
+
+    /// Like [`Devices::get_aftermarket_device_image`], but returns the response as sent by the
+    /// server. The image is not JSON, so unlike the other `_raw` methods this one does not
+    /// return a [`Value`].
+    pub async fn get_aftermarket_device_image_raw(
+        &self,
+        token_id: &str,
+    ) -> Result<HttpResponse, DimoError> {
         let path = format!("/v1/aftermarket/device/{}/image", token_id);
 
         let request_params = RequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
@@ -486,17 +853,29 @@ impl Devices {
             headers: None,
         };
 
-        make_request(request_params).await
+        self.client.make_raw_request(request_params).await
     }
 
     pub async fn get_aftermarket_device_metadata_by_address(
         &self,
         address: &str,
-    ) -> Result<Value, Box<dyn Error>> {
+    ) -> Result<AftermarketDeviceMetadata, DimoError> {
+        let response = self
+            .get_aftermarket_device_metadata_by_address_raw(address)
+            .await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Devices::get_aftermarket_device_metadata_by_address`], but returns the response as JSON.
+    pub async fn get_aftermarket_device_metadata_by_address_raw(
+        &self,
+        address: &str,
+    ) -> Result<Value, DimoError> {
         let path = format!("/v1/aftermarket/device/by-address/{}", address);
 
         let request_params = RequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
@@ -504,6 +883,130 @@ impl Devices {
             headers: None,
         };
 
-        make_request(request_params).await
+        self.client.make_request(request_params).await
+    }
+}
+
+/// Signs an EIP-712 payload returned by the devices API, returning the `0x`-prefixed signature.
+async fn sign_payload(payload: &TypedData, signer: &dyn Signer) -> Result<String, DimoError> {
+    Ok(signer.sign_typed_data(payload).await?.to_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::signer::{verify_typed_data, Signature, TypedData};
+    use crate::utils::testing::{bearer, body, dimo, ok, signer, SIGNER_ADDRESS};
+    use crate::utils::transport::{HttpResponse, MockTransport};
+    use reqwest::header::CONTENT_TYPE;
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn claims_with_both_signatures() {
+        let payload = json!({
+            "types": { "ClaimAftermarketDeviceSign": [
+                { "name": "aftermarketDeviceNode", "type": "uint256" },
+                { "name": "owner", "type": "address" },
+            ] },
+            "primaryType": "ClaimAftermarketDeviceSign",
+            "domain": { "name": "DIMO", "version": "1", "chainId": 80001 },
+            "message": {
+                "aftermarketDeviceNode": "12",
+                "owner": SIGNER_ADDRESS,
+            },
+        });
+        let transport = Arc::new(MockTransport::new());
+        transport.push(ok(payload.clone()));
+        transport.push(ok(json!({})));
+        let dimo = dimo(&transport).await;
+
+        dimo.devices
+            .claim_aftermarket_device("serial-1", "0xdevice", &signer())
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, Method::POST);
+        assert!(requests[1]
+            .url
+            .ends_with("/v1/aftermarket/device/by-serial/serial-1/commands/claim"));
+        let sent = body(&requests[1]);
+        assert_eq!(sent["aftermarketDeviceSignature"], "0xdevice");
+        let signature = Signature::from_hex(sent["userSignature"].as_str().unwrap()).unwrap();
+        let payload = TypedData::from_value(payload).unwrap();
+        verify_typed_data(&payload, &signature, SIGNER_ADDRESS).unwrap();
+        assert_eq!(sent.as_object().map(|o| o.len()), Some(2));
+    }
+
+    #[tokio::test]
+    async fn deletes_vehicles_and_integrations() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(HttpResponse::new(StatusCode::NO_CONTENT, ""))
+            .push(HttpResponse::new(StatusCode::NO_CONTENT, ""));
+        let dimo = dimo(&transport).await;
+
+        dimo.devices.delete_vehicle("2VU7Bq6J").await.unwrap();
+        dimo.devices
+            .delete_integration("2VU7Bq6J", "smartcar")
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        for request in requests.iter() {
+            assert_eq!(request.method, Method::DELETE);
+            assert_eq!(bearer(request), "Bearer DEV");
+            assert!(request.body.is_none());
+        }
+        assert!(requests[0].url.ends_with("/v1/user/devices/2VU7Bq6J"));
+        assert!(requests[1]
+            .url
+            .ends_with("/v1/user/devices/2VU7Bq6J/integrations/smartcar"));
+    }
+
+    #[tokio::test]
+    async fn returns_null_for_an_empty_delete_response() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(HttpResponse::new(StatusCode::NO_CONTENT, ""));
+        let dimo = dimo(&transport).await;
+
+        let response = dimo.devices.delete_vehicle_raw("2VU7Bq6J").await.unwrap();
+        assert_eq!(response, serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn returns_the_aftermarket_device_image_as_bytes() {
+        let png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let transport = Arc::new(MockTransport::new());
+        let mut response = HttpResponse::new(StatusCode::OK, png.clone());
+        response
+            .headers
+            .insert(CONTENT_TYPE, "image/png".parse().unwrap());
+        transport
+            .push(response)
+            .push(HttpResponse::new(StatusCode::NOT_FOUND, "not found"));
+        let dimo = dimo(&transport).await;
+
+        let image = dimo
+            .devices
+            .get_aftermarket_device_image("7")
+            .await
+            .unwrap();
+        assert_eq!(image.content_type.as_deref(), Some("image/png"));
+        assert_eq!(image.data, png);
+        assert_eq!(
+            transport.requests()[0].url,
+            "https://devices-api.dev.dimo.zone/v1/aftermarket/device/7/image"
+        );
+
+        let err = dimo
+            .devices
+            .get_aftermarket_device_image("8")
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
     }
 }
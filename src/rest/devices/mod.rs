@@ -1,2 +1,12 @@
+#[allow(clippy::module_inception)]
 pub mod devices;
+pub mod models;
 pub use devices::Devices;
+pub use models::{
+    AftermarketDevice, AftermarketDeviceImage, AftermarketDeviceMetadata, ClaimRequest,
+    ClaimSignatures, ClaimingPayload, CommandResponse, CreateVehicleFromSmartcarRequest,
+    CreateVehicleFromVinRequest, CreateVehicleRequest, ErrorCode, ErrorCodesQuery,
+    ErrorCodesResponse, MintPayload, MintRequest, NftAttribute, PairingPayload, PairingRequest,
+    RegisterUserDeviceResponse, SubmitErrorCodesRequest, UserDevice, UserDeviceIntegration,
+    VehicleNft,
+};
@@ -0,0 +1,294 @@
+use crate::signer::TypedData;
+use crate::utils::transport::HttpResponse;
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+
+/// The EIP-712 payload returned by [`super::Devices::get_minting_payload`].
+pub type MintPayload = TypedData;
+/// The EIP-712 payload returned by the pairing and unpairing endpoints.
+pub type PairingPayload = TypedData;
+/// The EIP-712 payload returned by [`super::Devices::get_claiming_payload`].
+pub type ClaimingPayload = TypedData;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateVehicleRequest {
+    pub country_code: String,
+    pub device_definition_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateVehicleFromSmartcarRequest {
+    pub code: String,
+    pub country_code: String,
+    #[serde(rename = "redirectURI")]
+    pub redirect_uri: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateVehicleFromVinRequest {
+    pub vin: String,
+    pub country_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_protocol: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterUserDeviceResponse {
+    pub user_device_id: String,
+    pub device_definition_id: String,
+}
+
+/// A vehicle registered with the devices API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDevice {
+    pub id: String,
+    pub vin: Option<String>,
+    #[serde(default)]
+    pub vin_confirmed: bool,
+    pub name: Option<String>,
+    pub country_code: Option<String>,
+    pub device_definition_id: Option<String>,
+    #[serde(default)]
+    pub integrations: Vec<UserDeviceIntegration>,
+    pub opted_in_at: Option<String>,
+    pub nft: Option<VehicleNft>,
+}
+
+/// Responses that wrap a [`UserDevice`] as `{"userDevice": ...}`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UserDeviceEnvelope {
+    pub user_device: UserDevice,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDeviceIntegration {
+    pub integration_id: String,
+    /// E.g. `Active` or `Pending`.
+    pub status: Option<String>,
+    pub external_id: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// The vehicle NFT minted for a [`UserDevice`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleNft {
+    pub token_id: Option<u64>,
+    pub owner_address: Option<String>,
+    pub tx_hash: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MintRequest {
+    /// The `0x`-prefixed signature of the [`MintPayload`].
+    pub signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_data_transparent: Option<String>,
+}
+
+impl MintRequest {
+    pub fn new(signature: &str) -> Self {
+        Self {
+            signature: signature.to_string(),
+            image_data: None,
+            image_data_transparent: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MintBody<'a> {
+    pub mint_request: &'a MintRequest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimRequest {
+    pub claim_request: String,
+}
+
+/// Both signatures of a [`ClaimingPayload`], which the claim needs before it is accepted.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimSignatures {
+    /// The `0x`-prefixed signature of the owner.
+    pub user_signature: String,
+    /// The `0x`-prefixed signature of the aftermarket device, as produced by its firmware.
+    pub aftermarket_device_signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingRequest {
+    /// The `0x`-prefixed signature of the [`PairingPayload`].
+    pub user_signature: String,
+}
+
+/// The acknowledgement of a vehicle command such as [`super::Devices::lock_doors`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandResponse {
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitErrorCodesRequest {
+    pub query_device_error_codes: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorCodesResponse {
+    #[serde(default)]
+    pub queries: Vec<ErrorCodesQuery>,
+}
+
+/// The diagnostic trouble codes read from a vehicle at one point in time.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorCodesQuery {
+    #[serde(default)]
+    pub error_codes: Vec<ErrorCode>,
+    pub requested_at: Option<String>,
+    pub cleared_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorCode {
+    pub code: String,
+    pub description: Option<String>,
+}
+
+/// The ERC-721 metadata of an aftermarket device NFT, such as an AutoPi or Macaron.
+/// Details such as the serial number are in [`AftermarketDeviceMetadata::attributes`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct AftermarketDeviceMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    #[serde(default)]
+    pub attributes: Vec<NftAttribute>,
+}
+
+impl AftermarketDeviceMetadata {
+    /// The value of the attribute named `trait_type`, e.g. `Serial Number`.
+    pub fn attribute(&self, trait_type: &str) -> Option<&serde_json::Value> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.trait_type == trait_type)
+            .map(|attribute| &attribute.value)
+    }
+}
+
+/// The name the NFT metadata of an aftermarket device is also known by.
+pub type AftermarketDevice = AftermarketDeviceMetadata;
+
+/// The picture of an aftermarket device NFT. The endpoint answers with the image itself,
+/// usually a PNG, rather than with JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AftermarketDeviceImage {
+    /// The `Content-Type` of the image, e.g. `image/png`.
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl From<HttpResponse> for AftermarketDeviceImage {
+    fn from(response: HttpResponse) -> Self {
+        Self {
+            content_type: response
+                .headers
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            data: response.body,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NftAttribute {
+    pub trait_type: String,
+    pub value: serde_json::Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::fixture;
+    use serde_json::json;
+
+    #[test]
+    fn parses_registered_devices() {
+        let response: RegisterUserDeviceResponse =
+            serde_json::from_value(fixture("devices/register_user_device.json")).unwrap();
+        assert_eq!(response.device_definition_id, "ford_bronco_2022");
+
+        let envelope: UserDeviceEnvelope =
+            serde_json::from_value(fixture("devices/user_device.json")).unwrap();
+        let device = envelope.user_device;
+        assert!(device.vin_confirmed);
+        assert_eq!(device.nft.unwrap().token_id, Some(18934));
+        assert_eq!(device.integrations[0].status.as_deref(), Some("Active"));
+        // An integration without a status still parses.
+        assert_eq!(device.integrations[1].status, None);
+    }
+
+    #[test]
+    fn parses_payloads() {
+        let payload: MintPayload =
+            serde_json::from_value(fixture("devices/minting_payload.json")).unwrap();
+        assert_eq!(payload.primary_type, "MintVehicleSign");
+        payload.hash().unwrap();
+    }
+
+    #[test]
+    fn parses_command_responses() {
+        let response: CommandResponse =
+            serde_json::from_value(fixture("devices/command.json")).unwrap();
+        assert_eq!(
+            response.request_id.as_deref(),
+            Some("2VU7Bq6Jb6N5ffXzFQ0lWVMS8cA")
+        );
+        let response: CommandResponse = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(response.request_id, None);
+    }
+
+    #[test]
+    fn parses_error_codes() {
+        let response: ErrorCodesResponse =
+            serde_json::from_value(fixture("devices/error_codes.json")).unwrap();
+        assert_eq!(response.queries.len(), 2);
+        assert_eq!(response.queries[0].error_codes[1].code, "P0300");
+        assert!(response.queries[1].cleared_at.is_some());
+
+        let query: ErrorCodesQuery =
+            serde_json::from_value(fixture("devices/error_codes_query.json")).unwrap();
+        assert_eq!(query.error_codes[0].code, "P0113");
+    }
+
+    #[test]
+    fn parses_aftermarket_device_metadata() {
+        let metadata: AftermarketDeviceMetadata =
+            serde_json::from_value(fixture("devices/aftermarket_device.json")).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("lazy crane welcome"));
+        assert_eq!(
+            metadata.attribute("Serial Number"),
+            Some(&json!("7000A1204"))
+        );
+        assert_eq!(metadata.attribute("IMEI"), None);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod privileges;
+#[allow(clippy::module_inception)]
 pub mod tokenexchange;
-pub use tokenexchange::{TokenExchange, TokenExchangeParams};
+pub mod vehicletokens;
+pub use privileges::Privilege;
+pub use tokenexchange::{TokenExchange, TokenExchangeParams, VehicleToken, VehicleTokenClaims};
+pub use vehicletokens::VehicleTokenStore;
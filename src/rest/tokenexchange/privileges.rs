@@ -0,0 +1,138 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Vehicle privileges that can be granted to a developer license through SACD
+/// and requested in a token exchange.
+///
+/// See <https://docs.dimo.org/developer-platform/api-references/token-exchange-api/token-exchange-endpoints>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Privilege {
+    AllTimeNonLocationData,
+    Commands,
+    CurrentLocation,
+    AllTimeLocation,
+    VinCredential,
+    LiveDataStreams,
+    RawData,
+    ApproximateLocation,
+}
+
+impl Privilege {
+    pub const ALL: [Privilege; 8] = [
+        Privilege::AllTimeNonLocationData,
+        Privilege::Commands,
+        Privilege::CurrentLocation,
+        Privilege::AllTimeLocation,
+        Privilege::VinCredential,
+        Privilege::LiveDataStreams,
+        Privilege::RawData,
+        Privilege::ApproximateLocation,
+    ];
+
+    /// The numeric privilege ID used by the token exchange API.
+    pub fn id(self) -> i32 {
+        match self {
+            Privilege::AllTimeNonLocationData => 1,
+            Privilege::Commands => 2,
+            Privilege::CurrentLocation => 3,
+            Privilege::AllTimeLocation => 4,
+            Privilege::VinCredential => 5,
+            Privilege::LiveDataStreams => 6,
+            Privilege::RawData => 7,
+            Privilege::ApproximateLocation => 8,
+        }
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Privilege::AllTimeNonLocationData => "All-time, non-location data",
+            Privilege::Commands => "Commands",
+            Privilege::CurrentLocation => "Current location",
+            Privilege::AllTimeLocation => "All-time location",
+            Privilege::VinCredential => "View VIN credentials",
+            Privilege::LiveDataStreams => "Live data streams",
+            Privilege::RawData => "Raw data",
+            Privilege::ApproximateLocation => "Approximate location",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Privilege::AllTimeNonLocationData => {
+                "Read current and historical vehicle data, excluding location."
+            }
+            Privilege::Commands => "Send commands such as locking and unlocking the doors.",
+            Privilege::CurrentLocation => "Read the current location of the vehicle.",
+            Privilege::AllTimeLocation => "Read current and historical vehicle locations.",
+            Privilege::VinCredential => "Create and read VIN verifiable credentials.",
+            Privilege::LiveDataStreams => "Subscribe to live data streams.",
+            Privilege::RawData => "Read raw, unprocessed data from the vehicle's devices.",
+            Privilege::ApproximateLocation => "Read the approximate location of the vehicle.",
+        }
+    }
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.id())
+    }
+}
+
+impl Serialize for Privilege {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for Privilege {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = i32::deserialize(deserializer)?;
+        Privilege::from_id(id)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown privilege id {}", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn keeps_the_token_exchange_ids() {
+        let ids: Vec<i32> = Privilege::ALL.iter().map(|p| p.id()).collect();
+        assert_eq!(ids, [1, 2, 3, 4, 5, 6, 7, 8]);
+        for privilege in Privilege::ALL {
+            assert_eq!(Privilege::from_id(privilege.id()), Some(privilege));
+        }
+        assert_eq!(Privilege::from_id(0), None);
+        assert_eq!(Privilege::from_id(9), None);
+    }
+
+    #[test]
+    fn serializes_as_ids() {
+        let privileges = [
+            Privilege::AllTimeNonLocationData,
+            Privilege::AllTimeLocation,
+            Privilege::ApproximateLocation,
+        ];
+        assert_eq!(serde_json::to_value(privileges).unwrap(), json!([1, 4, 8]));
+        let parsed: Vec<Privilege> = serde_json::from_value(json!([2, 5, 6, 7, 3])).unwrap();
+        assert_eq!(
+            parsed,
+            [
+                Privilege::Commands,
+                Privilege::VinCredential,
+                Privilege::LiveDataStreams,
+                Privilege::RawData,
+                Privilege::CurrentLocation,
+            ]
+        );
+
+        let err = serde_json::from_value::<Privilege>(json!(42)).unwrap_err();
+        assert!(err.to_string().contains("unknown privilege id 42"));
+    }
+}
@@ -1,32 +1,134 @@
-use crate::utils::request::{make_auth_request, AuthRequestParams};
+use super::privileges::Privilege;
+use crate::jwt;
+use crate::utils::request::{AuthRequestParams, HttpClient, TokenType};
+use crate::utils::DimoError;
 use reqwest::Method;
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[derive(Clone)]
 pub struct TokenExchange {
     base_url: String,
+    client: HttpClient,
     contract_address: String,
 }
 
 pub struct TokenExchangeParams {
-    pub privileges: Vec<i32>,
+    pub privileges: Vec<Privilege>,
     pub token_id: i32,
 }
 
+/// A vehicle JWT issued by the token exchange, together with its decoded claims.
+#[derive(Debug, Clone)]
+pub struct VehicleToken {
+    token: String,
+    claims: VehicleTokenClaims,
+}
+
+/// The claims of a vehicle JWT.
+#[derive(Deserialize, Debug, Clone)]
+pub struct VehicleTokenClaims {
+    #[serde(deserialize_with = "deserialize_token_id")]
+    pub token_id: i32,
+    pub contract_address: String,
+    #[serde(rename = "privilege_ids", deserialize_with = "deserialize_privileges")]
+    pub privileges: Vec<Privilege>,
+    /// Expiry as seconds since the Unix epoch.
+    pub exp: u64,
+    pub iss: String,
+}
+
+#[derive(Deserialize)]
+struct ExchangeResponse {
+    token: String,
+}
+
+impl VehicleToken {
+    /// Decodes the claims of `token` without verifying its signature.
+    ///
+    /// Use [`crate::jwt::JwtVerifier`] to check the signature as well.
+    pub fn from_jwt(token: &str) -> Result<Self, DimoError> {
+        Ok(Self {
+            token: token.to_string(),
+            claims: jwt::decode(token)?,
+        })
+    }
+
+    /// The JWT, sent as the bearer token of vehicle-scoped requests.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn claims(&self) -> &VehicleTokenClaims {
+        &self.claims
+    }
+
+    pub fn token_id(&self) -> i32 {
+        self.claims.token_id
+    }
+
+    /// The vehicle NFT contract the token ID belongs to.
+    pub fn contract_address(&self) -> &str {
+        &self.claims.contract_address
+    }
+
+    pub fn privileges(&self) -> &[Privilege] {
+        &self.claims.privileges
+    }
+
+    pub fn has_privilege(&self, privilege: Privilege) -> bool {
+        self.claims.privileges.contains(&privilege)
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.claims.iss
+    }
+
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.claims.exp)
+    }
+}
+
+/// Token IDs are encoded as strings by the token exchange, but numbers are accepted too.
+fn deserialize_token_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(|n| i32::try_from(n).ok())
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid token id {}", n))),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid token id {}",
+            other
+        ))),
+    }
+}
+
+/// Skips privilege IDs this SDK does not know about, since no method can require them.
+fn deserialize_privileges<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Privilege>, D::Error> {
+    let ids: Vec<i32> = Option::deserialize(deserializer)?.unwrap_or_default();
+    Ok(ids.into_iter().filter_map(Privilege::from_id).collect())
+}
+
 impl TokenExchange {
-    pub fn new(base_url: &str, contract_address: &str) -> Self {
+    pub fn new(base_url: &str, contract_address: &str, client: HttpClient) -> Self {
         Self {
             base_url: base_url.to_string(),
             contract_address: contract_address.to_string(),
+            client,
         }
     }
 
+    /// Exchanges the developer JWT for a vehicle JWT granting `privileges` on `token_id`.
     pub async fn exchange(
         &self,
         token_id: i32,
-        privileges: Vec<i32>,
-    ) -> Result<Value, Box<dyn Error>> {
+        privileges: &[Privilege],
+    ) -> Result<VehicleToken, DimoError> {
         let path = "/v1/tokens/exchange".to_string();
         let mut body: HashMap<String, Value> = HashMap::new();
         body.insert(
@@ -34,22 +136,25 @@ impl TokenExchange {
             Value::String(self.contract_address.to_string()),
         );
         let privileges_as_values: Vec<Value> = privileges
-            .into_iter()
-            .map(|num| Value::Number(serde_json::Number::from(num)))
+            .iter()
+            .map(|privilege| Value::from(privilege.id()))
             .collect();
         body.insert("privileges".to_string(), Value::Array(privileges_as_values));
         body.insert("tokenId".to_string(), Value::from(token_id));
 
         let request_params = AuthRequestParams {
             method: Method::POST,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: Some(body),
             headers: None,
-            token_type: "developer".to_string(),
+            token_type: TokenType::Developer,
         };
 
-        make_auth_request(request_params).await
+        let response = self.client.make_auth_request(request_params).await?;
+        let response: ExchangeResponse = serde_json::from_value(response)?;
+        VehicleToken::from_jwt(&response.token)
     }
 }
@@ -0,0 +1,209 @@
+use super::privileges::Privilege;
+use super::tokenexchange::{TokenExchange, VehicleToken};
+use crate::utils::DimoError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+type CacheKey = (i32, Vec<Privilege>);
+type CacheSlot = Arc<tokio::sync::Mutex<Option<VehicleToken>>>;
+
+/// Caches vehicle JWTs per `(token_id, privileges)` and fetches missing or expiring ones
+/// through [`TokenExchange::exchange`], authenticated with the current developer JWT.
+///
+/// Each request asks only for the privileges it declares, so a vehicle shared with a
+/// narrow set of privileges still works for the methods those privileges cover.
+pub struct VehicleTokenStore {
+    exchange: TokenExchange,
+    refresh_margin: Duration,
+    slots: Mutex<HashMap<CacheKey, CacheSlot>>,
+}
+
+impl VehicleTokenStore {
+    pub fn new(exchange: TokenExchange) -> Self {
+        Self {
+            exchange,
+            refresh_margin: Duration::from_secs(30),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Exchange a new token this long before the cached one expires. Defaults to 30 seconds.
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// Returns a vehicle JWT for `token_id` granting `privileges`, exchanging a new one on a
+    /// miss or near expiry.
+    ///
+    /// Fails with [`DimoError::MissingPrivileges`] if the issued token does not grant all of
+    /// `privileges`, e.g. because the vehicle owner did not share them with this developer
+    /// license.
+    pub async fn token(
+        &self,
+        token_id: i32,
+        privileges: &[Privilege],
+    ) -> Result<VehicleToken, DimoError> {
+        let key = cache_key(token_id, privileges);
+        let slot = self.slot(&key);
+        let mut cached = slot.lock().await;
+        if let Some(ref current) = *cached {
+            if SystemTime::now() + self.refresh_margin < current.expires_at() {
+                check_privileges(current, &key.1)?;
+                return Ok(current.clone());
+            }
+        }
+
+        let fresh = self.exchange.exchange(token_id, &key.1).await?;
+        *cached = Some(fresh.clone());
+        check_privileges(&fresh, &key.1)?;
+        Ok(fresh)
+    }
+
+    /// Drops the cached token for `token_id` and `privileges` if it is still `rejected`.
+    pub async fn invalidate(&self, token_id: i32, privileges: &[Privilege], rejected: &str) {
+        let slot = self.slot(&cache_key(token_id, privileges));
+        let mut cached = slot.lock().await;
+        if matches!(*cached, Some(ref c) if c.token() == rejected) {
+            *cached = None;
+        }
+    }
+
+    fn slot(&self, key: &CacheKey) -> CacheSlot {
+        self.slots
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone()
+    }
+}
+
+/// Sorts and deduplicates `privileges` so equivalent requests share a cache entry.
+fn cache_key(token_id: i32, privileges: &[Privilege]) -> CacheKey {
+    let mut privileges = privileges.to_vec();
+    privileges.sort();
+    privileges.dedup();
+    (token_id, privileges)
+}
+
+fn check_privileges(token: &VehicleToken, required: &[Privilege]) -> Result<(), DimoError> {
+    let missing: Vec<Privilege> = required
+        .iter()
+        .filter(|privilege| !token.has_privilege(**privilege))
+        .copied()
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(DimoError::MissingPrivileges {
+            token_id: token.token_id(),
+            missing,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{self, bearer, body, vehicle_token_response};
+    use crate::utils::transport::MockTransport;
+    use reqwest::Method;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn caches_one_token_per_vehicle_and_privilege_set() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Method::POST,
+            "/v1/tokens/exchange",
+            vehicle_token_response(7, &[1, 2, 4]),
+        );
+        let dimo = testing::dimo(&transport).await;
+        let store = &dimo.vehicle_tokens;
+        use Privilege::{AllTimeLocation, AllTimeNonLocationData, Commands};
+
+        let first = store
+            .token(7, &[AllTimeNonLocationData, AllTimeLocation])
+            .await
+            .unwrap();
+        // The same privileges in another order, with a duplicate, share the entry.
+        let again = store
+            .token(
+                7,
+                &[AllTimeLocation, AllTimeNonLocationData, AllTimeLocation],
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.token(), again.token());
+        assert_eq!(transport.requests().len(), 1);
+
+        store.token(7, &[Commands]).await.unwrap();
+        store.token(8, &[Commands]).await.unwrap();
+        store.token(7, &[Commands]).await.unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(bearer(&requests[0]), "Bearer DEV");
+        let exchanged: Vec<_> = requests
+            .iter()
+            .map(|r| {
+                let body = body(r);
+                (body["tokenId"].clone(), body["privileges"].clone())
+            })
+            .collect();
+        assert_eq!(
+            exchanged,
+            [
+                (json!(7), json!([1, 4])),
+                (json!(7), json!([2])),
+                (json!(8), json!([2])),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn exchanges_again_after_invalidation() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Method::POST,
+            "/v1/tokens/exchange",
+            vehicle_token_response(7, &[2]),
+        );
+        let dimo = testing::dimo(&transport).await;
+        let store = &dimo.vehicle_tokens;
+
+        let token = store.token(7, &[Privilege::Commands]).await.unwrap();
+        store.invalidate(7, &[Privilege::Commands], "stale").await;
+        store.token(7, &[Privilege::Commands]).await.unwrap();
+        assert_eq!(transport.requests().len(), 1);
+
+        store
+            .invalidate(7, &[Privilege::Commands], token.token())
+            .await;
+        store.token(7, &[Privilege::Commands]).await.unwrap();
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_vehicle_did_not_grant_a_privilege() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Method::POST,
+            "/v1/tokens/exchange",
+            vehicle_token_response(7, &[1]),
+        );
+        let dimo = testing::dimo(&transport).await;
+
+        let err = dimo
+            .vehicle_tokens
+            .token(7, &[Privilege::AllTimeNonLocationData, Privilege::Commands])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DimoError::MissingPrivileges { token_id: 7, ref missing } if missing == &[Privilege::Commands]
+        ));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod models;
+#[allow(clippy::module_inception)]
 pub mod trips;
+pub use models::{Location, Trip, TripEndpoint, TripsResponse};
 pub use trips::Trips;
@@ -0,0 +1,63 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TripsResponse {
+    #[serde(default)]
+    pub trips: Vec<Trip>,
+    #[serde(default)]
+    pub current_page: u32,
+    #[serde(default)]
+    pub total_pages: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trip {
+    pub id: String,
+    pub start: TripEndpoint,
+    /// `None` while the trip is ongoing.
+    pub end: Option<TripEndpoint>,
+    /// Whether some of the trip's data points were lost.
+    #[serde(default)]
+    pub dropped_data: bool,
+}
+
+/// Where and when a [`Trip`] started or ended.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TripEndpoint {
+    /// RFC 3339 timestamp.
+    pub time: String,
+    pub location: Option<Location>,
+    /// Set when the location was inferred rather than reported by the vehicle.
+    pub estimated_location: Option<Location>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::fixture;
+
+    #[test]
+    fn parses_trips() {
+        let response: TripsResponse = serde_json::from_value(fixture("trips/trips.json")).unwrap();
+        assert_eq!((response.current_page, response.total_pages), (1, 4));
+
+        let finished = &response.trips[0];
+        assert_eq!(finished.start.location.unwrap().latitude, 37.7749);
+        assert!(finished.end.is_some());
+
+        let ongoing = &response.trips[1];
+        assert!(ongoing.end.is_none());
+        assert!(ongoing.start.location.is_none());
+        assert!(ongoing.start.estimated_location.is_some());
+        assert!(!ongoing.dropped_data);
+    }
+}
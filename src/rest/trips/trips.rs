@@ -1,21 +1,41 @@
-use crate::utils::request::{make_auth_request, AuthRequestParams};
+use super::models::TripsResponse;
+use crate::rest::tokenexchange::Privilege;
+use crate::utils::request::{AuthRequestParams, HttpClient, TokenType};
+use crate::utils::DimoError;
 use reqwest::Method;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::error::Error;
+
+/// Privileges needed to list a vehicle's trips.
+const TRIP_PRIVILEGES: &[Privilege] = &[
+    Privilege::AllTimeNonLocationData,
+    Privilege::AllTimeLocation,
+];
 
 pub struct Trips {
     base_url: String,
+    client: HttpClient,
 }
 
 impl Trips {
-    pub fn new(base_url: &str) -> Self {
+    pub fn new(base_url: &str, client: HttpClient) -> Self {
         Self {
             base_url: base_url.to_string(),
+            client,
         }
     }
 
-    pub async fn list(&self, token_id: &str, page: Option<u32>) -> Result<Value, Box<dyn Error>> {
+    pub async fn list(
+        &self,
+        token_id: &str,
+        page: Option<u32>,
+    ) -> Result<TripsResponse, DimoError> {
+        let response = self.list_raw(token_id, page).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Trips::list`], but returns the response as JSON.
+    pub async fn list_raw(&self, token_id: &str, page: Option<u32>) -> Result<Value, DimoError> {
         let path = format!("/v1/vehicle/{}/trips", token_id);
 
         let mut query_params = HashMap::new();
@@ -25,14 +45,15 @@ impl Trips {
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: Some(query_params),
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(token_id, TRIP_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 }
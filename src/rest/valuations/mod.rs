@@ -1,2 +1,5 @@
+pub mod models;
+#[allow(clippy::module_inception)]
 pub mod valuations;
+pub use models::{Offer, OfferSet, OffersResponse, Valuation, ValuationsResponse};
 pub use valuations::Valuations;
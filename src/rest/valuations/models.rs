@@ -0,0 +1,89 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuationsResponse {
+    #[serde(default)]
+    pub valuation_sets: Vec<Valuation>,
+}
+
+/// A vendor's estimate of a vehicle's value.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Valuation {
+    pub vendor: Option<String>,
+    /// RFC 3339 timestamp of the estimate.
+    pub updated: Option<String>,
+    pub mileage: Option<f64>,
+    pub odometer: Option<f64>,
+    pub odometer_unit: Option<String>,
+    pub zip_code: Option<String>,
+    pub trade_in: Option<f64>,
+    pub trade_in_average: Option<f64>,
+    pub retail: Option<f64>,
+    pub retail_average: Option<f64>,
+    pub user_display_price: Option<f64>,
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OffersResponse {
+    #[serde(default)]
+    pub offer_sets: Vec<OfferSet>,
+}
+
+/// The offers gathered from one source at one point in time.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferSet {
+    pub source: Option<String>,
+    pub updated: Option<String>,
+    pub mileage: Option<f64>,
+    pub zip_code: Option<String>,
+    #[serde(default)]
+    pub offers: Vec<Offer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Offer {
+    pub vendor: Option<String>,
+    pub price: Option<f64>,
+    pub url: Option<String>,
+    /// Why the vendor could not make an offer.
+    pub error: Option<String>,
+    pub grade: Option<String>,
+    pub decline_reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::fixture;
+
+    #[test]
+    fn parses_valuations() {
+        let response: ValuationsResponse =
+            serde_json::from_value(fixture("valuations/valuations.json")).unwrap();
+        let [drivly, unnamed] = &response.valuation_sets[..] else {
+            panic!("expected two valuations");
+        };
+        assert_eq!(drivly.vendor.as_deref(), Some("drivly"));
+        assert_eq!(drivly.retail_average, Some(35875.5));
+        // A valuation without a vendor still parses.
+        assert_eq!(unnamed.vendor, None);
+        assert_eq!(unnamed.odometer_unit.as_deref(), Some("km"));
+    }
+
+    #[test]
+    fn parses_offers() {
+        let response: OffersResponse =
+            serde_json::from_value(fixture("valuations/offers.json")).unwrap();
+        let offers = &response.offer_sets[0].offers;
+        assert_eq!(offers.len(), 3);
+        assert_eq!(offers[0].price, Some(30500.0));
+        assert_eq!(offers[1].decline_reason.as_deref(), Some("mileage"));
+        assert!(offers[1].price.is_none());
+    }
+}
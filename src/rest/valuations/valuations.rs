@@ -1,64 +1,97 @@
-use crate::utils::request::{make_auth_request, AuthRequestParams};
+use super::models::{OffersResponse, ValuationsResponse};
+use crate::rest::tokenexchange::Privilege;
+use crate::utils::request::{AuthRequestParams, HttpClient, TokenType};
+use crate::utils::DimoError;
 use reqwest::Method;
 use serde_json::Value;
-use std::error::Error;
+
+/// Privileges needed to read a vehicle's valuations and offers.
+const VALUATION_PRIVILEGES: &[Privilege] = &[Privilege::AllTimeNonLocationData];
 
 pub struct Valuations {
     base_url: String,
+    client: HttpClient,
 }
 
 impl Valuations {
-    pub fn new(base_url: &str) -> Self {
+    pub fn new(base_url: &str, client: HttpClient) -> Self {
         Self {
             base_url: base_url.to_string(),
+            client,
         }
     }
 
-    pub async fn get_valuations(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    pub async fn get_valuations(
+        &self,
+        user_device_id: &str,
+    ) -> Result<ValuationsResponse, DimoError> {
+        let response = self.get_valuations_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Like [`Valuations::get_valuations`], but returns the response as JSON.
+    pub async fn get_valuations_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v2/vehicles/{}/valuations", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(user_device_id, VALUATION_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 
-    pub async fn get_instant_offers(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Requests instant offers for the vehicle; they are listed by [`Valuations::get_offers`]
+    /// once the vendors have answered.
+    pub async fn get_instant_offers(&self, user_device_id: &str) -> Result<(), DimoError> {
+        self.get_instant_offers_raw(user_device_id).await?;
+        Ok(())
+    }
+
+    /// Like [`Valuations::get_instant_offers`], but returns the response as JSON.
+    pub async fn get_instant_offers_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v2/vehicles/{}/instant-offer", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(user_device_id, VALUATION_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
+    }
+
+    pub async fn get_offers(&self, user_device_id: &str) -> Result<OffersResponse, DimoError> {
+        let response = self.get_offers_raw(user_device_id).await?;
+        Ok(serde_json::from_value(response)?)
     }
 
-    pub async fn get_offers(&self, user_device_id: &str) -> Result<Value, Box<dyn Error>> {
+    /// Like [`Valuations::get_offers`], but returns the response as JSON.
+    pub async fn get_offers_raw(&self, user_device_id: &str) -> Result<Value, DimoError> {
         let path = format!("/v2/vehicles/{}/offers", user_device_id);
 
         let request_params = AuthRequestParams {
             method: Method::GET,
+            idempotent: true,
             base_url: self.base_url.clone(),
             path,
             query_params: None,
             body: None,
             headers: None,
-            token_type: "vehicle".to_string(),
+            token_type: TokenType::vehicle(user_device_id, VALUATION_PRIVILEGES)?,
         };
 
-        make_auth_request(request_params).await
+        self.client.make_auth_request(request_params).await
     }
 }
@@ -0,0 +1,119 @@
+use super::LocalSigner;
+use crate::utils::DimoError;
+use secp256k1::PublicKey;
+use sha3::{Digest, Keccak256};
+
+/// The EIP-55 checksummed form of a 20 byte address.
+pub fn checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+
+    let mut checksummed = String::with_capacity(42);
+    checksummed.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        // A letter is upper-cased when the matching nibble of the hash is 8 or higher.
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        if nibble >= 8 {
+            checksummed.push(c.to_ascii_uppercase());
+        } else {
+            checksummed.push(c);
+        }
+    }
+    checksummed
+}
+
+/// Parses a hex address, with or without the `0x` prefix, and returns its EIP-55 form.
+///
+/// Mixed-case input must already carry a valid checksum.
+pub fn to_checksum_address(address: &str) -> Result<String, DimoError> {
+    let hex_part = address.strip_prefix("0x").unwrap_or(address);
+    let bytes: [u8; 20] = hex::decode(hex_part)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| DimoError::Signing(format!("invalid address '{}'", address)))?;
+
+    let checksummed = checksum_address(&bytes);
+    let mixed_case = hex_part.chars().any(|c| c.is_ascii_lowercase())
+        && hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && checksummed[2..] != *hex_part {
+        return Err(DimoError::Signing(format!(
+            "invalid EIP-55 checksum in address '{}'",
+            address
+        )));
+    }
+    Ok(checksummed)
+}
+
+/// The EIP-55 checksummed address of a hex encoded private key.
+pub fn address_from_private_key(private_key: &str) -> Result<String, DimoError> {
+    Ok(LocalSigner::from_hex(private_key)?
+        .checksummed_address()
+        .to_string())
+}
+
+/// The EIP-55 checksummed address of a public key.
+pub(crate) fn address_from_public_key(public_key: &PublicKey) -> String {
+    // The address is the last 20 bytes of the hash of the uncompressed key, without its 0x04 tag.
+    let hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    checksum_address(&address)
+}
+
+/// Compares two addresses ignoring case and the `0x` prefix.
+pub(crate) fn same_address(a: &str, b: &str) -> bool {
+    let a = a.strip_prefix("0x").unwrap_or(a);
+    let b = b.strip_prefix("0x").unwrap_or(b);
+    a.eq_ignore_ascii_case(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{PRIVATE_KEY, SIGNER_ADDRESS};
+
+    /// The test vectors of EIP-55.
+    const CHECKSUMMED: [&str; 8] = [
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+        "0xde709f2102306220921060314715629080e2fb77",
+        "0x27b1fdb04752bbc536007a920d24acb045561c26",
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn checksums_the_eip55_vectors() {
+        for address in CHECKSUMMED {
+            let bytes: [u8; 20] = hex::decode(&address[2..]).unwrap().try_into().unwrap();
+            assert_eq!(checksum_address(&bytes), address);
+            assert_eq!(to_checksum_address(address).unwrap(), address);
+            assert_eq!(
+                to_checksum_address(&address[2..].to_lowercase()).unwrap(),
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_mixed_case_addresses_with_a_bad_checksum() {
+        // The first vector with the case of one letter flipped.
+        let err = to_checksum_address("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap_err();
+        assert!(matches!(err, DimoError::Signing(_)));
+        assert!(to_checksum_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").is_ok());
+        assert!(to_checksum_address("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED").is_ok());
+        assert!(to_checksum_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea").is_err());
+        assert!(to_checksum_address("0xzzaeb6053f3e94c9b9a09f33669435e7ef1beaed").is_err());
+    }
+
+    #[test]
+    fn derives_the_address_of_a_private_key() {
+        assert_eq!(
+            address_from_private_key(PRIVATE_KEY).unwrap(),
+            SIGNER_ADDRESS
+        );
+        assert!(address_from_private_key("not a key").is_err());
+    }
+}
@@ -0,0 +1,495 @@
+use crate::utils::DimoError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha3::{Digest, Keccak256};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// EIP-712 typed data, as returned by the minting, pairing, unpairing and claiming
+/// payload endpoints of [`crate::rest::devices::Devices`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    pub primary_type: String,
+    pub domain: Map<String, Value>,
+    pub message: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// The fields `EIP712Domain` may have, in the order the standard defines them.
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+impl TypedData {
+    pub fn from_value(value: Value) -> Result<Self, DimoError> {
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// The digest that gets signed: `keccak256(0x1901 || domainSeparator || hashStruct(message))`.
+    pub fn hash(&self) -> Result<[u8; 32], DimoError> {
+        let mut hasher = Keccak256::new();
+        hasher.update([0x19, 0x01]);
+        hasher.update(self.domain_separator()?);
+        hasher.update(self.hash_struct(&self.primary_type, &Value::Object(self.message.clone()))?);
+        Ok(hasher.finalize().into())
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; 32], DimoError> {
+        self.hash_struct(DOMAIN_TYPE, &Value::Object(self.domain.clone()))
+    }
+
+    /// `keccak256(typeHash || encodeData(data))` for a value of struct type `type_name`.
+    pub fn hash_struct(&self, type_name: &str, data: &Value) -> Result<[u8; 32], DimoError> {
+        let fields = self.fields(type_name)?;
+        let data = data
+            .as_object()
+            .ok_or_else(|| eip712_error(format!("value of type {} is not an object", type_name)))?;
+
+        let mut hasher = Keccak256::new();
+        hasher.update(Keccak256::digest(self.encode_type(type_name)?.as_bytes()));
+        for field in fields.iter() {
+            let value = data.get(&field.name).ok_or_else(|| {
+                eip712_error(format!("missing field {}.{}", type_name, field.name))
+            })?;
+            hasher.update(self.encode_value(&field.type_name, value)?);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// The type signature, e.g. `Mail(Person from,Person to,string contents)Person(string name,address wallet)`.
+    pub fn encode_type(&self, type_name: &str) -> Result<String, DimoError> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut dependencies)?;
+        dependencies.remove(type_name);
+
+        let mut encoded = String::new();
+        for name in std::iter::once(type_name).chain(dependencies.iter().map(String::as_str)) {
+            let fields: Vec<String> = self
+                .fields(name)?
+                .iter()
+                .map(|field| format!("{} {}", field.type_name, field.name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, fields.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    /// The fields of `type_name`; `EIP712Domain` is derived from the domain when it is not
+    /// listed in `types`.
+    fn fields(&self, type_name: &str) -> Result<Vec<TypedDataField>, DimoError> {
+        if let Some(fields) = self.types.get(type_name) {
+            return Ok(fields.clone());
+        }
+        if type_name == DOMAIN_TYPE {
+            return Ok(DOMAIN_FIELDS
+                .iter()
+                .filter(|(name, _)| self.domain.contains_key(*name))
+                .map(|(name, type_name)| TypedDataField {
+                    name: name.to_string(),
+                    type_name: type_name.to_string(),
+                })
+                .collect());
+        }
+        Err(eip712_error(format!("unknown type {}", type_name)))
+    }
+
+    fn collect_dependencies(
+        &self,
+        type_name: &str,
+        found: &mut BTreeSet<String>,
+    ) -> Result<(), DimoError> {
+        if found.contains(type_name) {
+            return Ok(());
+        }
+        found.insert(type_name.to_string());
+        for field in self.fields(type_name)? {
+            let base = base_type(&field.type_name);
+            if self.types.contains_key(base) {
+                self.collect_dependencies(base, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_value(&self, type_name: &str, value: &Value) -> Result<[u8; 32], DimoError> {
+        if let Some(element_type) = array_element_type(type_name) {
+            let items = value.as_array().ok_or_else(|| {
+                eip712_error(format!("value of type {} is not an array", type_name))
+            })?;
+            let mut hasher = Keccak256::new();
+            for item in items {
+                hasher.update(self.encode_value(element_type, item)?);
+            }
+            return Ok(hasher.finalize().into());
+        }
+        if self.types.contains_key(type_name) {
+            return self.hash_struct(type_name, value);
+        }
+
+        match type_name {
+            "string" => {
+                let value = value
+                    .as_str()
+                    .ok_or_else(|| eip712_error(format!("expected a string, got {}", value)))?;
+                Ok(Keccak256::digest(value.as_bytes()).into())
+            }
+            "bytes" => Ok(Keccak256::digest(decode_hex(value)?).into()),
+            "bool" => {
+                let value = match value {
+                    Value::Bool(b) => *b,
+                    Value::String(s) if s == "true" || s == "false" => s == "true",
+                    _ => return Err(eip712_error(format!("expected a bool, got {}", value))),
+                };
+                Ok(encode_uint(value as u64))
+            }
+            "address" => {
+                let bytes = decode_hex(value)?;
+                if bytes.len() != 20 {
+                    return Err(eip712_error(format!("invalid address {}", value)));
+                }
+                let mut word = [0u8; 32];
+                word[12..].copy_from_slice(&bytes);
+                Ok(word)
+            }
+            _ if type_name.starts_with("bytes") => {
+                let size: usize = type_name[5..]
+                    .parse()
+                    .map_err(|_| eip712_error(format!("unknown type {}", type_name)))?;
+                let bytes = decode_hex(value)?;
+                if size == 0 || size > 32 || bytes.len() > size {
+                    return Err(eip712_error(format!(
+                        "invalid {} value {}",
+                        type_name, value
+                    )));
+                }
+                let mut word = [0u8; 32];
+                word[..bytes.len()].copy_from_slice(&bytes);
+                Ok(word)
+            }
+            _ if type_name.starts_with("uint") => {
+                let bits = integer_bits(type_name, 4)?;
+                let (negative, magnitude) = parse_integer(value)?;
+                if negative || bit_length(&magnitude) > bits {
+                    return Err(out_of_range(value, type_name));
+                }
+                Ok(magnitude)
+            }
+            _ if type_name.starts_with("int") => {
+                let bits = integer_bits(type_name, 3)?;
+                let (negative, magnitude) = parse_integer(value)?;
+                // intN holds -2^(N-1) ..= 2^(N-1) - 1.
+                let length = bit_length(&magnitude);
+                let is_min = negative && length == bits && count_ones(&magnitude) == 1;
+                if length > bits - 1 && !is_min {
+                    return Err(out_of_range(value, type_name));
+                }
+                Ok(if negative {
+                    twos_complement(magnitude)
+                } else {
+                    magnitude
+                })
+            }
+            _ => Err(eip712_error(format!("unknown type {}", type_name))),
+        }
+    }
+}
+
+/// `Person[]` or `Person[2]` -> `Person`, otherwise `None`.
+fn array_element_type(type_name: &str) -> Option<&str> {
+    if !type_name.ends_with(']') {
+        return None;
+    }
+    type_name.rfind('[').map(|i| &type_name[..i])
+}
+
+fn base_type(type_name: &str) -> &str {
+    type_name.split('[').next().unwrap_or(type_name)
+}
+
+/// The bit size of `uintN`/`intN`, where `prefix_len` is the length of `uint`/`int`.
+fn integer_bits(type_name: &str, prefix_len: usize) -> Result<usize, DimoError> {
+    let bits = match &type_name[prefix_len..] {
+        "" => 256,
+        size => size
+            .parse()
+            .map_err(|_| eip712_error(format!("unknown type {}", type_name)))?,
+    };
+    if bits == 0 || bits > 256 || bits % 8 != 0 {
+        return Err(eip712_error(format!("unknown type {}", type_name)));
+    }
+    Ok(bits)
+}
+
+fn encode_uint(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Parses a JSON number, or a decimal or `0x` hex string, into its sign and 256-bit
+/// big-endian magnitude.
+fn parse_integer(value: &Value) -> Result<(bool, [u8; 32]), DimoError> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        _ => return Err(eip712_error(format!("expected an integer, got {}", value))),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+
+    let (radix, digits) = match digits.strip_prefix("0x") {
+        Some(hex) => (16u32, hex),
+        None => (10u32, digits),
+    };
+    if digits.is_empty() {
+        return Err(eip712_error(format!("expected an integer, got {}", value)));
+    }
+
+    let mut word = [0u8; 32];
+    for c in digits.chars() {
+        let mut carry = c
+            .to_digit(radix)
+            .ok_or_else(|| eip712_error(format!("expected an integer, got {}", value)))?;
+        for byte in word.iter_mut().rev() {
+            let next = *byte as u32 * radix + carry;
+            *byte = next as u8;
+            carry = next >> 8;
+        }
+        if carry != 0 {
+            return Err(eip712_error(format!("{} does not fit in 256 bits", value)));
+        }
+    }
+    Ok((negative && word.iter().any(|b| *b != 0), word))
+}
+
+/// The number of significant bits of a big-endian word.
+fn bit_length(word: &[u8; 32]) -> usize {
+    word.iter()
+        .position(|b| *b != 0)
+        .map(|i| (32 - i) * 8 - word[i].leading_zeros() as usize)
+        .unwrap_or(0)
+}
+
+fn count_ones(word: &[u8; 32]) -> u32 {
+    word.iter().map(|b| b.count_ones()).sum()
+}
+
+fn out_of_range(value: &Value, type_name: &str) -> DimoError {
+    eip712_error(format!("{} out of range for {}", value, type_name))
+}
+
+fn twos_complement(mut word: [u8; 32]) -> [u8; 32] {
+    for byte in word.iter_mut() {
+        *byte = !*byte;
+    }
+    for byte in word.iter_mut().rev() {
+        let (next, overflow) = byte.overflowing_add(1);
+        *byte = next;
+        if !overflow {
+            break;
+        }
+    }
+    word
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>, DimoError> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| eip712_error(format!("expected a hex string, got {}", value)))?;
+    Ok(hex::decode(text.strip_prefix("0x").unwrap_or(text))?)
+}
+
+fn eip712_error(msg: String) -> DimoError {
+    DimoError::Signing(format!("eip-712: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The `Mail` example of the EIP-712 specification.
+    fn mail() -> TypedData {
+        TypedData::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" },
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" },
+                ],
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC",
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!",
+            },
+        }))
+        .unwrap()
+    }
+
+    /// A single field struct `Value(<type_name> value)` holding `value`.
+    fn single(type_name: &str, value: Value) -> TypedData {
+        TypedData::from_value(json!({
+            "types": { "Value": [{ "name": "value", "type": type_name }] },
+            "primaryType": "Value",
+            "domain": { "name": "test" },
+            "message": { "value": value },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn hashes_the_mail_example() {
+        let mail = mail();
+
+        assert_eq!(
+            mail.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(mail.domain_separator().unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(
+                mail.hash_struct("Mail", &Value::Object(mail.message.clone()))
+                    .unwrap()
+            ),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hex::encode(mail.hash().unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[test]
+    fn hashes_nested_structs_and_arrays() {
+        let mail = TypedData::from_value(json!({
+            "types": {
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallets", "type": "address[]" },
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person[]" },
+                    { "name": "contents", "type": "string" },
+                ],
+                "Group": [
+                    { "name": "name", "type": "string" },
+                    { "name": "members", "type": "Person[]" },
+                ],
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC",
+            },
+            "message": {
+                "from": {
+                    "name": "Cow",
+                    "wallets": [
+                        "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826",
+                        "0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF",
+                    ],
+                },
+                "to": [{
+                    "name": "Bob",
+                    "wallets": [
+                        "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB",
+                        "0xB0BdaBea57B0BDABeA57b0bdABEA57b0BDabEa57",
+                        "0xB0B0b0b0b0b0B000000000000000000000000000",
+                    ],
+                }],
+                "contents": "Hello, Bob!",
+            },
+        }))
+        .unwrap();
+
+        assert_eq!(
+            mail.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person[] to,string contents)Person(string name,address[] wallets)"
+        );
+        assert_eq!(
+            mail.encode_type("Group").unwrap(),
+            "Group(string name,Person[] members)Person(string name,address[] wallets)"
+        );
+        assert_eq!(
+            hex::encode(mail.hash().unwrap()),
+            "a85c2e2b118698e88db68a8105b794a8cc7cec074e89ef991cb4f5f533819cc2"
+        );
+    }
+
+    #[test]
+    fn rejects_integers_out_of_range() {
+        for (type_name, value) in [
+            ("uint8", json!(255)),
+            ("uint8", json!("0xff")),
+            ("uint256", json!(0)),
+            ("int8", json!(127)),
+            ("int8", json!(-128)),
+            ("int256", json!("-1")),
+        ] {
+            assert!(
+                single(type_name, value.clone()).hash().is_ok(),
+                "{} {}",
+                type_name,
+                value
+            );
+        }
+
+        for (type_name, value) in [
+            ("uint8", json!(256)),
+            ("uint8", json!(-1)),
+            ("uint16", json!("0x10000")),
+            ("int8", json!(128)),
+            ("int8", json!(-129)),
+            ("int16", json!(-32769)),
+        ] {
+            let err = single(type_name, value.clone()).hash().unwrap_err();
+            assert!(err.to_string().contains("out of range"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn encodes_negative_integers_as_twos_complement() {
+        let data = single("int8", json!(-1));
+        assert_eq!(data.encode_value("int8", &json!(-1)).unwrap(), [0xff; 32]);
+        assert_eq!(data.encode_value("int8", &json!(-128)).unwrap()[31], 0x80);
+    }
+}
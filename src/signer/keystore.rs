@@ -0,0 +1,285 @@
+use super::LocalSigner;
+use crate::utils::DimoError;
+use aes::Aes128;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use serde::Deserialize;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::path::Path;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+/// The key length every V3 keystore uses: 16 bytes of AES key and 16 bytes of MAC key.
+const DKLEN: usize = 32;
+/// Upper bounds for the key derivation parameters, so that a crafted keystore cannot make
+/// decryption hang or exhaust memory. They are well above the values geth and clef write.
+const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+const MAX_SCRYPT_P: u32 = 16;
+
+/// An Ethereum V3 keystore, as written by geth, clef or MetaMask exports.
+#[derive(Deserialize)]
+struct Keystore {
+    #[serde(alias = "Crypto")]
+    crypto: KeystoreCrypto,
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct KeystoreCrypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    #[serde(flatten)]
+    kdf: Kdf,
+    mac: String,
+}
+
+#[derive(Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+enum Kdf {
+    Scrypt {
+        dklen: usize,
+        n: u64,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        dklen: usize,
+        c: u32,
+        prf: String,
+        salt: String,
+    },
+}
+
+impl LocalSigner {
+    /// Decrypts the private key from an Ethereum V3 keystore file.
+    pub fn from_keystore(path: impl AsRef<Path>, password: &str) -> Result<Self, DimoError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            DimoError::Config(format!(
+                "could not read keystore '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_keystore_json(&json, password)
+    }
+
+    /// Decrypts the private key from the JSON of an Ethereum V3 keystore.
+    pub fn from_keystore_json(json: &str, password: &str) -> Result<Self, DimoError> {
+        let keystore: Keystore = serde_json::from_str(json)?;
+        if keystore.version != 3 {
+            return Err(keystore_error(format!(
+                "unsupported version {}",
+                keystore.version
+            )));
+        }
+        let crypto = keystore.crypto;
+        if crypto.cipher != "aes-128-ctr" {
+            return Err(keystore_error(format!(
+                "unsupported cipher '{}'",
+                crypto.cipher
+            )));
+        }
+
+        let key = derive_key(&crypto.kdf, password.as_bytes())?;
+        let mut private_key = hex::decode(&crypto.ciphertext)?;
+
+        // The MAC covers the second half of the derived key and the ciphertext, so a wrong
+        // password is detected before decrypting.
+        let mac = Keccak256::new()
+            .chain_update(&key[16..32])
+            .chain_update(&private_key)
+            .finalize();
+        if hex::decode(&crypto.mac)? != mac.as_slice() {
+            return Err(keystore_error(
+                "MAC mismatch, the password is probably wrong".to_string(),
+            ));
+        }
+
+        let iv = hex::decode(&crypto.cipherparams.iv)?;
+        let mut cipher = Aes128Ctr::new_from_slices(&key[..16], &iv)
+            .map_err(|e| keystore_error(format!("invalid iv: {}", e)))?;
+        cipher.apply_keystream(&mut private_key);
+
+        Self::from_bytes(&private_key)
+    }
+}
+
+fn derive_key(kdf: &Kdf, password: &[u8]) -> Result<Vec<u8>, DimoError> {
+    match kdf {
+        Kdf::Scrypt {
+            dklen,
+            n,
+            r,
+            p,
+            salt,
+        } => {
+            check_dklen(*dklen)?;
+            if !n.is_power_of_two() {
+                return Err(keystore_error(format!(
+                    "scrypt n {} is not a power of two",
+                    n
+                )));
+            }
+            // scrypt needs 128 * n * r bytes of memory.
+            let memory = 128u64.saturating_mul(*n).saturating_mul(*r as u64);
+            if memory > MAX_SCRYPT_MEMORY || *p > MAX_SCRYPT_P {
+                return Err(keystore_error(format!(
+                    "scrypt parameters n={} r={} p={} exceed the supported maximum",
+                    n, r, p
+                )));
+            }
+            let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p, *dklen)
+                .map_err(|e| keystore_error(format!("invalid scrypt parameters: {}", e)))?;
+            let mut key = vec![0u8; *dklen];
+            scrypt::scrypt(password, &hex::decode(salt)?, &params, &mut key)
+                .map_err(|e| keystore_error(format!("scrypt failed: {}", e)))?;
+            Ok(key)
+        }
+        Kdf::Pbkdf2 {
+            dklen,
+            c,
+            prf,
+            salt,
+        } => {
+            if prf != "hmac-sha256" {
+                return Err(keystore_error(format!("unsupported prf '{}'", prf)));
+            }
+            check_dklen(*dklen)?;
+            if *c > MAX_PBKDF2_ROUNDS {
+                return Err(keystore_error(format!(
+                    "pbkdf2 iteration count {} exceeds the supported maximum of {}",
+                    c, MAX_PBKDF2_ROUNDS
+                )));
+            }
+            let mut key = vec![0u8; *dklen];
+            pbkdf2::pbkdf2_hmac::<Sha256>(password, &hex::decode(salt)?, *c, &mut key);
+            Ok(key)
+        }
+    }
+}
+
+fn check_dklen(dklen: usize) -> Result<(), DimoError> {
+    if dklen != DKLEN {
+        return Err(keystore_error(format!(
+            "dklen must be {}, got {}",
+            DKLEN, dklen
+        )));
+    }
+    Ok(())
+}
+
+fn keystore_error(msg: String) -> DimoError {
+    DimoError::Signing(format!("keystore: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::Signer;
+    use crate::utils::testing::{PRIVATE_KEY, SIGNER_ADDRESS};
+    use serde_json::{json, Value};
+
+    /// The PBKDF2 test vector of the Web3 Secret Storage definition.
+    fn pbkdf2_keystore() -> Value {
+        json!({
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                "kdf": "pbkdf2",
+                "kdfparams": {
+                    "c": 262144,
+                    "dklen": 32,
+                    "prf": "hmac-sha256",
+                    "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                },
+                "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "version": 3
+        })
+    }
+
+    /// Encrypts [`PRIVATE_KEY`] with light scrypt parameters.
+    fn scrypt_keystore(password: &str) -> Value {
+        let salt = [7u8; 32];
+        let iv = [9u8; 16];
+        let mut key = [0u8; DKLEN];
+        let params = scrypt::Params::new(10, 8, 1, DKLEN).unwrap();
+        scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key).unwrap();
+
+        let mut ciphertext = hex::decode(PRIVATE_KEY).unwrap();
+        Aes128Ctr::new_from_slices(&key[..16], &iv)
+            .unwrap()
+            .apply_keystream(&mut ciphertext);
+        let mac = Keccak256::new()
+            .chain_update(&key[16..])
+            .chain_update(&ciphertext)
+            .finalize();
+        json!({
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": hex::encode(iv) },
+                "ciphertext": hex::encode(ciphertext),
+                "kdf": "scrypt",
+                "kdfparams": { "dklen": 32, "n": 1024, "r": 8, "p": 1, "salt": hex::encode(salt) },
+                "mac": hex::encode(mac)
+            },
+            "version": 3
+        })
+    }
+
+    fn decrypt(keystore: &Value, password: &str) -> Result<LocalSigner, DimoError> {
+        LocalSigner::from_keystore_json(&keystore.to_string(), password)
+    }
+
+    #[tokio::test]
+    async fn decrypts_the_pbkdf2_test_vector() {
+        let signer = decrypt(&pbkdf2_keystore(), "testpassword").unwrap();
+        assert_eq!(signer.address().await.unwrap(), SIGNER_ADDRESS);
+    }
+
+    #[tokio::test]
+    async fn decrypts_a_scrypt_keystore() {
+        let signer = decrypt(&scrypt_keystore("hunter2"), "hunter2").unwrap();
+        assert_eq!(signer.address().await.unwrap(), SIGNER_ADDRESS);
+    }
+
+    #[test]
+    fn rejects_a_wrong_password() {
+        let err = decrypt(&scrypt_keystore("hunter2"), "hunter3").unwrap_err();
+        assert!(err.to_string().contains("MAC mismatch"));
+    }
+
+    #[test]
+    fn rejects_unbounded_kdf_parameters() {
+        let mut keystore = pbkdf2_keystore();
+        keystore["crypto"]["kdfparams"]["dklen"] = json!(1u64 << 40);
+        let err = decrypt(&keystore, "testpassword").unwrap_err();
+        assert!(err.to_string().contains("dklen must be 32"));
+
+        let mut keystore = pbkdf2_keystore();
+        keystore["crypto"]["kdfparams"]["c"] = json!(u32::MAX);
+        let err = decrypt(&keystore, "testpassword").unwrap_err();
+        assert!(err.to_string().contains("iteration count"));
+
+        let mut keystore = scrypt_keystore("hunter2");
+        keystore["crypto"]["kdfparams"]["n"] = json!(1u64 << 40);
+        let err = decrypt(&keystore, "hunter2").unwrap_err();
+        assert!(err.to_string().contains("exceed the supported maximum"));
+
+        let mut keystore = scrypt_keystore("hunter2");
+        keystore["crypto"]["kdfparams"]["dklen"] = json!(64);
+        let err = decrypt(&keystore, "hunter2").unwrap_err();
+        assert!(err.to_string().contains("dklen must be 32"));
+    }
+}
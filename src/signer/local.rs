@@ -0,0 +1,80 @@
+use super::address::address_from_public_key;
+use super::{hash_message, Signature, Signer, TypedData};
+use crate::utils::DimoError;
+use async_trait::async_trait;
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use std::fmt;
+
+/// Signs with a secp256k1 private key held in memory.
+#[derive(Clone)]
+pub struct LocalSigner {
+    secret_key: SecretKey,
+    address: String,
+}
+
+impl LocalSigner {
+    pub fn from_secret_key(secret_key: SecretKey) -> Self {
+        let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key);
+        Self {
+            secret_key,
+            address: address_from_public_key(&public_key),
+        }
+    }
+
+    pub fn from_bytes(private_key: &[u8]) -> Result<Self, DimoError> {
+        Ok(Self::from_secret_key(SecretKey::from_slice(private_key)?))
+    }
+
+    /// Parses a hex encoded private key, with or without the `0x` prefix.
+    pub fn from_hex(private_key: &str) -> Result<Self, DimoError> {
+        let bytes = hex::decode(private_key.strip_prefix("0x").unwrap_or(private_key))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// The EIP-55 checksummed address of the key, available without awaiting
+    /// [`Signer::address`].
+    pub fn checksummed_address(&self) -> &str {
+        &self.address
+    }
+
+    /// Signs a 32 byte digest as is.
+    pub fn sign_hash(&self, hash: &[u8; 32]) -> Signature {
+        let message = Message::from_digest(*hash);
+        let signature =
+            Secp256k1::signing_only().sign_ecdsa_recoverable(&message, &self.secret_key);
+        let (recovery_id, compact) = signature.serialize_compact();
+
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&compact[..32]);
+        s.copy_from_slice(&compact[32..]);
+        Signature {
+            r,
+            s,
+            v: recovery_id.to_i32() as u8 + 27,
+        }
+    }
+}
+
+impl fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    async fn address(&self) -> Result<String, DimoError> {
+        Ok(self.address.clone())
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, DimoError> {
+        Ok(self.sign_hash(&hash_message(message)))
+    }
+
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<Signature, DimoError> {
+        Ok(self.sign_hash(&data.hash()?))
+    }
+}
@@ -0,0 +1,123 @@
+//! Signers for the web3 challenge and other messages signed on behalf of a developer
+//! license, so the private key does not have to be passed around as a string.
+
+mod address;
+mod eip712;
+mod keystore;
+mod local;
+mod recover;
+mod remote;
+
+pub(crate) use address::same_address;
+pub use address::{address_from_private_key, checksum_address, to_checksum_address};
+pub use eip712::{TypedData, TypedDataField};
+pub use local::LocalSigner;
+pub use recover::{
+    recover_address, recover_message_address, recover_typed_data_address, verify_message,
+    verify_typed_data,
+};
+pub use remote::RemoteSigner;
+
+use crate::utils::DimoError;
+use async_trait::async_trait;
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use std::sync::Arc;
+
+/// Something that holds an Ethereum key and can sign with it.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// The EIP-55 checksummed address of the signing key.
+    async fn address(&self) -> Result<String, DimoError>;
+
+    /// Signs `message` as an EIP-191 personal message, i.e. prefixed with
+    /// `"\x19Ethereum Signed Message:\n" + len(message)`.
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, DimoError>;
+
+    /// Signs EIP-712 typed data, i.e. the digest returned by [`TypedData::hash`].
+    ///
+    /// Signers that cannot sign typed data keep the default, which fails.
+    async fn sign_typed_data(&self, _data: &TypedData) -> Result<Signature, DimoError> {
+        Err(DimoError::Signing("unsupported".to_string()))
+    }
+}
+
+#[async_trait]
+impl<S: Signer + ?Sized> Signer for Arc<S> {
+    async fn address(&self) -> Result<String, DimoError> {
+        (**self).address().await
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, DimoError> {
+        (**self).sign_message(message).await
+    }
+
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<Signature, DimoError> {
+        (**self).sign_typed_data(data).await
+    }
+}
+
+/// A recoverable secp256k1 signature with `v` as 27 or 28.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    pub v: u8,
+}
+
+impl Signature {
+    /// Parses the 65 byte `r || s || v` encoding, accepting `v` as 0/1 or 27/28.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DimoError> {
+        if bytes.len() != 65 {
+            return Err(DimoError::Signing(format!(
+                "signature must be 65 bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let v = match bytes[64] {
+            0 | 1 => bytes[64] + 27,
+            27 | 28 => bytes[64],
+            v => return Err(DimoError::Signing(format!("invalid recovery id {}", v))),
+        };
+
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..64]);
+        Ok(Self { r, s, v })
+    }
+
+    /// Parses a hex encoded signature, with or without the `0x` prefix.
+    pub fn from_hex(signature: &str) -> Result<Self, DimoError> {
+        let bytes = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = self.v;
+        bytes
+    }
+
+    /// The `0x`-prefixed hex encoding of [`Signature::to_bytes`].
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.to_bytes()))
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// The EIP-191 personal message hash of `message`.
+pub fn hash_message(message: &[u8]) -> [u8; 32] {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    let mut hasher = Keccak256::new();
+    hasher.update(prefix.as_bytes());
+    hasher.update(message);
+    hasher.finalize().into()
+}
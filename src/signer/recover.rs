@@ -0,0 +1,186 @@
+use super::address::address_from_public_key;
+use super::{hash_message, same_address, Signature, TypedData};
+use crate::utils::DimoError;
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, Secp256k1};
+
+/// Half the order of the secp256k1 curve. Signatures with a higher `s` are the malleable
+/// twin of a valid low-s signature and are rejected, as in EIP-2.
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Recovers the EIP-55 checksummed address that signed the 32 byte digest `hash`.
+pub fn recover_address(hash: &[u8; 32], signature: &Signature) -> Result<String, DimoError> {
+    if signature.s > HALF_ORDER {
+        return Err(DimoError::Signing(
+            "signature has a high s value and is malleable".to_string(),
+        ));
+    }
+    let recovery_id = match signature.v {
+        0 | 1 => signature.v,
+        27 | 28 => signature.v - 27,
+        v => return Err(DimoError::Signing(format!("invalid recovery id {}", v))),
+    };
+
+    let mut compact = [0u8; 64];
+    compact[..32].copy_from_slice(&signature.r);
+    compact[32..].copy_from_slice(&signature.s);
+    let recoverable =
+        RecoverableSignature::from_compact(&compact, RecoveryId::from_i32(recovery_id as i32)?)?;
+    let public_key =
+        Secp256k1::verification_only().recover_ecdsa(&Message::from_digest(*hash), &recoverable)?;
+    Ok(address_from_public_key(&public_key))
+}
+
+/// Recovers the address that signed `message` as an EIP-191 personal message, e.g. with
+/// [`crate::rest::auth::AuthClient::sign_challenge`].
+pub fn recover_message_address(message: &[u8], signature: &Signature) -> Result<String, DimoError> {
+    recover_address(&hash_message(message), signature)
+}
+
+/// Recovers the address that signed EIP-712 typed data.
+pub fn recover_typed_data_address(
+    data: &TypedData,
+    signature: &Signature,
+) -> Result<String, DimoError> {
+    recover_address(&data.hash()?, signature)
+}
+
+/// Checks that `signature` over the personal message `message` was made by `expected`.
+pub fn verify_message(
+    message: &[u8],
+    signature: &Signature,
+    expected: &str,
+) -> Result<(), DimoError> {
+    check_signer(recover_message_address(message, signature)?, expected)
+}
+
+/// Checks that `signature` over `data` was made by `expected`, e.g. before forwarding a
+/// pairing signature to [`crate::rest::devices::Devices::sign_pairing_payload`].
+pub fn verify_typed_data(
+    data: &TypedData,
+    signature: &Signature,
+    expected: &str,
+) -> Result<(), DimoError> {
+    check_signer(recover_typed_data_address(data, signature)?, expected)
+}
+
+fn check_signer(recovered: String, expected: &str) -> Result<(), DimoError> {
+    if !same_address(&recovered, expected) {
+        return Err(DimoError::SignerMismatch {
+            expected: expected.to_string(),
+            recovered,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{signer, SIGNER_ADDRESS};
+    use serde_json::json;
+
+    /// The order of the secp256k1 curve.
+    const ORDER: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+        0x41, 0x41,
+    ];
+
+    /// `ORDER - s`, the `s` of the malleable twin of a signature.
+    fn negate(s: &[u8; 32]) -> [u8; 32] {
+        let mut negated = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let mut byte = ORDER[i] as i16 - s[i] as i16 - borrow;
+            borrow = if byte < 0 { 1 } else { 0 };
+            if byte < 0 {
+                byte += 256;
+            }
+            negated[i] = byte as u8;
+        }
+        negated
+    }
+
+    fn typed_data() -> TypedData {
+        TypedData::from_value(json!({
+            "types": { "Pair": [{ "name": "vehicleNode", "type": "uint256" }] },
+            "primaryType": "Pair",
+            "domain": { "name": "DIMO", "version": "1", "chainId": 80002 },
+            "message": { "vehicleNode": "42" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn recovers_with_either_recovery_id_convention() {
+        let hash = hash_message(b"hello");
+        let signature = signer().sign_hash(&hash);
+        assert!(signature.v == 27 || signature.v == 28);
+        assert_eq!(recover_address(&hash, &signature).unwrap(), SIGNER_ADDRESS);
+
+        let zero_based = Signature {
+            v: signature.v - 27,
+            ..signature
+        };
+        assert_eq!(recover_address(&hash, &zero_based).unwrap(), SIGNER_ADDRESS);
+    }
+
+    #[test]
+    fn rejects_unknown_recovery_ids() {
+        let hash = hash_message(b"hello");
+        let signature = Signature {
+            v: 29,
+            ..signer().sign_hash(&hash)
+        };
+        assert!(matches!(
+            recover_address(&hash, &signature),
+            Err(DimoError::Signing(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_high_s_signatures() {
+        let hash = hash_message(b"hello");
+        let signature = signer().sign_hash(&hash);
+        let twin = Signature {
+            s: negate(&signature.s),
+            v: if signature.v == 27 { 28 } else { 27 },
+            ..signature
+        };
+        assert!(twin.s > HALF_ORDER);
+        assert!(matches!(
+            recover_address(&hash, &twin),
+            Err(DimoError::Signing(_))
+        ));
+    }
+
+    #[test]
+    fn reports_the_recovered_address_of_another_signer() {
+        const OTHER: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let message = b"hello";
+        let signature = signer().sign_hash(&hash_message(message));
+        verify_message(message, &signature, &SIGNER_ADDRESS.to_lowercase()).unwrap();
+        match verify_message(message, &signature, OTHER) {
+            Err(DimoError::SignerMismatch {
+                expected,
+                recovered,
+            }) => {
+                assert_eq!(expected, OTHER);
+                assert_eq!(recovered, SIGNER_ADDRESS);
+            }
+            result => panic!("expected a signer mismatch, got {:?}", result),
+        }
+
+        let data = typed_data();
+        let signature = signer().sign_hash(&data.hash().unwrap());
+        verify_typed_data(&data, &signature, SIGNER_ADDRESS).unwrap();
+        assert!(matches!(
+            verify_typed_data(&data, &signature, OTHER),
+            Err(DimoError::SignerMismatch { .. })
+        ));
+    }
+}
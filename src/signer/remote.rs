@@ -0,0 +1,240 @@
+use super::{same_address, to_checksum_address, Signature, Signer, TypedData};
+use crate::utils::request::{HttpClient, RequestParams};
+use crate::utils::DimoError;
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Signs through the JSON-RPC API of an external signer, so the key never enters this
+/// process.
+///
+/// Any server that implements `eth_accounts`, `eth_sign` and `eth_signTypedData_v4` works,
+/// e.g. a local development node such as anvil or hardhat. clef does not serve `eth_sign`.
+/// Errors returned by the node surface as [`DimoError::JsonRpc`].
+pub struct RemoteSigner {
+    url: String,
+    client: HttpClient,
+    address: Option<String>,
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, client: HttpClient) -> Self {
+        Self {
+            url: url.to_string(),
+            client,
+            address: None,
+        }
+    }
+
+    /// Signs with `address` instead of the first account returned by `eth_accounts`. Fails
+    /// with [`DimoError::Signing`] when the node does not hold `address`.
+    pub async fn with_address(mut self, address: &str) -> Result<Self, DimoError> {
+        let address = to_checksum_address(address)?;
+        if !self
+            .accounts()
+            .await?
+            .iter()
+            .any(|account| same_address(account, &address))
+        {
+            return Err(DimoError::Signing(format!(
+                "remote signer does not hold {}",
+                address
+            )));
+        }
+        self.address = Some(address);
+        Ok(self)
+    }
+
+    /// The addresses the node signs for, from `eth_accounts`.
+    async fn accounts(&self) -> Result<Vec<String>, DimoError> {
+        let accounts = self.call("eth_accounts", Vec::new()).await?;
+        serde_json::from_value(accounts.clone()).map_err(|_| DimoError::JsonRpc {
+            method: "eth_accounts".to_string(),
+            code: None,
+            message: format!("unexpected result {}", accounts),
+        })
+    }
+
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, DimoError> {
+        let mut body: HashMap<String, Value> = HashMap::new();
+        body.insert("jsonrpc".to_string(), Value::from("2.0"));
+        body.insert("id".to_string(), Value::from(1));
+        body.insert("method".to_string(), Value::from(method));
+        body.insert("params".to_string(), Value::Array(params));
+
+        let request_params = RequestParams {
+            method: Method::POST,
+            idempotent: true,
+            base_url: self.url.clone(),
+            path: String::new(),
+            query_params: None,
+            body: Some(body),
+            headers: None,
+        };
+
+        let mut response = self.client.make_request(request_params).await?;
+        if let Some(error) = response.get("error") {
+            return Err(DimoError::JsonRpc {
+                method: method.to_string(),
+                code: error.get("code").and_then(Value::as_i64),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+            });
+        }
+        Ok(response["result"].take())
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn address(&self) -> Result<String, DimoError> {
+        if let Some(ref address) = self.address {
+            return Ok(address.clone());
+        }
+
+        let accounts = self.accounts().await?;
+        let address = accounts
+            .first()
+            .ok_or_else(|| DimoError::Signing("remote signer has no accounts".to_string()))?;
+        to_checksum_address(address)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, DimoError> {
+        let address = self.address().await?;
+        let data = format!("0x{}", hex::encode(message));
+
+        // `eth_sign` applies the personal message prefix itself.
+        let signature = self
+            .call("eth_sign", vec![Value::from(address), Value::from(data)])
+            .await?;
+        parse_signature(signature)
+    }
+
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<Signature, DimoError> {
+        let address = self.address().await?;
+        let data = serde_json::to_value(data)?;
+
+        let signature = self
+            .call("eth_signTypedData_v4", vec![Value::from(address), data])
+            .await?;
+        parse_signature(signature)
+    }
+}
+
+fn parse_signature(signature: Value) -> Result<Signature, DimoError> {
+    let signature = signature
+        .as_str()
+        .ok_or_else(|| DimoError::Signing(format!("remote signer returned {}", signature)))?;
+    Signature::from_hex(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::recover_message_address;
+    use crate::utils::retry::RetryPolicy;
+    use crate::utils::testing::{body, ok, signer, SIGNER_ADDRESS};
+    use crate::utils::transport::MockTransport;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const OTHER_ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    fn remote(transport: &Arc<MockTransport>) -> RemoteSigner {
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(RetryPolicy::none());
+        RemoteSigner::new("http://localhost:8545", client)
+    }
+
+    fn result(result: Value) -> crate::utils::transport::HttpResponse {
+        ok(json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+    }
+
+    #[tokio::test]
+    async fn uses_the_first_account_of_the_node() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(result(json!([
+            SIGNER_ADDRESS.to_lowercase(),
+            OTHER_ADDRESS
+        ])));
+
+        assert_eq!(remote(&transport).address().await.unwrap(), SIGNER_ADDRESS);
+        let sent = body(&transport.requests()[0]);
+        assert_eq!(sent["method"], "eth_accounts");
+        assert_eq!(sent["params"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn signs_messages_with_eth_sign() {
+        let message = b"localhost:8080 wants you to sign in";
+        let signature = signer().sign_message(message).await.unwrap();
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(result(json!([SIGNER_ADDRESS])))
+            .push(result(json!(signature.to_hex())));
+
+        let signed = remote(&transport).sign_message(message).await.unwrap();
+        assert_eq!(
+            recover_message_address(message, &signed).unwrap(),
+            SIGNER_ADDRESS
+        );
+        let sent = body(&transport.requests()[1]);
+        assert_eq!(sent["method"], "eth_sign");
+        assert_eq!(
+            sent["params"],
+            json!([SIGNER_ADDRESS, format!("0x{}", hex::encode(message))])
+        );
+    }
+
+    #[tokio::test]
+    async fn signs_only_with_addresses_the_node_holds() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(result(json!([SIGNER_ADDRESS, OTHER_ADDRESS])))
+            .push(result(json!([SIGNER_ADDRESS])));
+
+        let chosen = remote(&transport)
+            .with_address(&OTHER_ADDRESS.to_lowercase())
+            .await
+            .unwrap();
+        assert_eq!(chosen.address().await.unwrap(), OTHER_ADDRESS);
+
+        let err = remote(&transport)
+            .with_address(OTHER_ADDRESS)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, DimoError::Signing(_)));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn surfaces_node_errors_as_json_rpc_errors() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(result(json!([SIGNER_ADDRESS])))
+            .push(ok(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32601, "message": "the method eth_sign does not exist" },
+            })));
+
+        let err = remote(&transport).sign_message(b"hello").await.unwrap_err();
+        match err {
+            DimoError::JsonRpc {
+                method,
+                code,
+                message,
+            } => {
+                assert_eq!(method, "eth_sign");
+                assert_eq!(code, Some(-32601));
+                assert_eq!(message, "the method eth_sign does not exist");
+            }
+            err => panic!("expected a JSON-RPC error, got {:?}", err),
+        }
+    }
+}
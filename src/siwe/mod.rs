@@ -0,0 +1,459 @@
+//! Sign-In With Ethereum ([EIP-4361](https://eips.ethereum.org/EIPS/eip-4361)) messages.
+//!
+//! The web3 challenge returned by [`crate::rest::auth::AuthClient::generate_challenge`] is
+//! a SIWE message; [`crate::rest::auth::TokenManager`] parses and validates it before it
+//! is signed.
+
+use crate::signer::{same_address, to_checksum_address};
+use crate::utils::DimoError;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+const PREAMBLE: &str = " wants you to sign in with your Ethereum account:";
+
+/// A parsed or newly built SIWE message. Its [`fmt::Display`] output is the text to sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiweMessage {
+    /// The URI scheme of the origin, e.g. `https`, when the message names one.
+    pub scheme: Option<String>,
+    /// The authority requesting the signing, e.g. `example.com` or `localhost:8080`.
+    pub domain: String,
+    /// The EIP-55 checksummed address performing the signing.
+    pub address: String,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: SystemTime,
+    pub expiration_time: Option<SystemTime>,
+    pub not_before: Option<SystemTime>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+/// What [`SiweMessage::validate`] checks besides the expiration and not-before times.
+#[derive(Debug, Clone, Default)]
+pub struct SiweValidation {
+    /// Accepted domains. Compared by authority, so `https://example.com/callback` accepts
+    /// a message for `example.com`.
+    pub domain: Option<Vec<String>>,
+    pub address: Option<String>,
+    pub chain_id: Option<u64>,
+    /// The time to check the message against, now when `None`.
+    pub time: Option<SystemTime>,
+}
+
+impl SiweMessage {
+    /// A version 1 message issued now, with a random nonce.
+    pub fn new(domain: &str, address: &str, uri: &str, chain_id: u64) -> Result<Self, DimoError> {
+        Ok(Self {
+            scheme: None,
+            domain: domain.to_string(),
+            address: to_checksum_address(address)?,
+            statement: None,
+            uri: uri.to_string(),
+            version: "1".to_string(),
+            chain_id,
+            nonce: generate_nonce(),
+            issued_at: SystemTime::now(),
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: Vec::new(),
+        })
+    }
+
+    pub fn with_statement(mut self, statement: &str) -> Self {
+        self.statement = Some(statement.to_string());
+        self
+    }
+
+    pub fn with_nonce(mut self, nonce: &str) -> Self {
+        self.nonce = nonce.to_string();
+        self
+    }
+
+    pub fn with_expiration_time(mut self, expiration_time: SystemTime) -> Self {
+        self.expiration_time = Some(expiration_time);
+        self
+    }
+
+    pub fn with_not_before(mut self, not_before: SystemTime) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: &str) -> Self {
+        self.request_id = Some(request_id.to_string());
+        self
+    }
+
+    pub fn with_resources(mut self, resources: Vec<String>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    pub fn parse(message: &str) -> Result<Self, DimoError> {
+        let mut lines = message.lines().peekable();
+
+        let origin = lines
+            .next()
+            .and_then(|line| line.strip_suffix(PREAMBLE))
+            .ok_or_else(|| siwe_error("missing the sign in preamble"))?;
+        let (scheme, domain) = match origin.split_once("://") {
+            Some((scheme, domain)) => (Some(scheme.to_string()), domain),
+            None => (None, origin),
+        };
+        if domain.is_empty() {
+            return Err(siwe_error("missing domain"));
+        }
+
+        let address = lines.next().ok_or_else(|| siwe_error("missing address"))?;
+        let address = to_checksum_address(address)
+            .map_err(|_| siwe_error(&format!("invalid address '{}'", address)))?;
+
+        while lines.next_if(|line| line.is_empty()).is_some() {}
+        let statement = lines
+            .next_if(|line| !line.starts_with("URI: "))
+            .map(str::to_string);
+        while lines.next_if(|line| line.is_empty()).is_some() {}
+
+        let mut fields = Fields::default();
+        while let Some(line) = lines.next() {
+            if line.is_empty() {
+                continue;
+            }
+            if line == "Resources:" {
+                while let Some(resource) = lines.next_if(|line| line.starts_with("- ")) {
+                    fields.resources.push(resource[2..].to_string());
+                }
+                continue;
+            }
+
+            let (tag, value) = line
+                .split_once(": ")
+                .ok_or_else(|| siwe_error(&format!("unexpected line '{}'", line)))?;
+            let slot = match tag {
+                "URI" => &mut fields.uri,
+                "Version" => &mut fields.version,
+                "Chain ID" => &mut fields.chain_id,
+                "Nonce" => &mut fields.nonce,
+                "Issued At" => &mut fields.issued_at,
+                "Expiration Time" => &mut fields.expiration_time,
+                "Not Before" => &mut fields.not_before,
+                "Request ID" => &mut fields.request_id,
+                _ => return Err(siwe_error(&format!("unexpected field '{}'", tag))),
+            };
+            *slot = Some(value.to_string());
+        }
+
+        let chain_id = required(fields.chain_id, "Chain ID")?;
+        Ok(Self {
+            scheme,
+            domain: domain.to_string(),
+            address,
+            statement,
+            uri: required(fields.uri, "URI")?,
+            version: required(fields.version, "Version")?,
+            chain_id: chain_id
+                .parse()
+                .map_err(|_| siwe_error(&format!("invalid chain id '{}'", chain_id)))?,
+            nonce: parse_nonce(required(fields.nonce, "Nonce")?)?,
+            issued_at: parse_time(&required(fields.issued_at, "Issued At")?)?,
+            expiration_time: fields
+                .expiration_time
+                .as_deref()
+                .map(parse_time)
+                .transpose()?,
+            not_before: fields.not_before.as_deref().map(parse_time).transpose()?,
+            request_id: fields.request_id,
+            resources: fields.resources,
+        })
+    }
+
+    /// Checks the message against `validation`, and that it is neither expired nor used
+    /// before its not-before time.
+    pub fn validate(&self, validation: &SiweValidation) -> Result<(), DimoError> {
+        if let Some(ref domains) = validation.domain {
+            let domain = authority(&self.domain);
+            if !domains.iter().any(|expected| authority(expected) == domain) {
+                return Err(siwe_error(&format!(
+                    "domain {} is not one of {}",
+                    self.domain,
+                    domains.join(", ")
+                )));
+            }
+        }
+        if let Some(ref address) = validation.address {
+            if !same_address(&self.address, address) {
+                return Err(siwe_error(&format!(
+                    "address {} does not match {}",
+                    self.address, address
+                )));
+            }
+        }
+        if let Some(chain_id) = validation.chain_id {
+            if self.chain_id != chain_id {
+                return Err(siwe_error(&format!(
+                    "chain id {} does not match {}",
+                    self.chain_id, chain_id
+                )));
+            }
+        }
+
+        let now = validation.time.unwrap_or_else(SystemTime::now);
+        if matches!(self.expiration_time, Some(expiration) if expiration <= now) {
+            return Err(siwe_error("message has expired"));
+        }
+        if matches!(self.not_before, Some(not_before) if now < not_before) {
+            return Err(siwe_error("message is not valid yet"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SiweMessage {
+    type Err = DimoError;
+
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        Self::parse(message)
+    }
+}
+
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref scheme) = self.scheme {
+            write!(f, "{}://", scheme)?;
+        }
+        writeln!(f, "{}{}", self.domain, PREAMBLE)?;
+        writeln!(f, "{}", self.address)?;
+        writeln!(f)?;
+        if let Some(ref statement) = self.statement {
+            writeln!(f, "{}", statement)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "URI: {}", self.uri)?;
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Chain ID: {}", self.chain_id)?;
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        write!(f, "Issued At: {}", format_time(self.issued_at))?;
+        if let Some(expiration_time) = self.expiration_time {
+            write!(f, "\nExpiration Time: {}", format_time(expiration_time))?;
+        }
+        if let Some(not_before) = self.not_before {
+            write!(f, "\nNot Before: {}", format_time(not_before))?;
+        }
+        if let Some(ref request_id) = self.request_id {
+            write!(f, "\nRequest ID: {}", request_id)?;
+        }
+        if !self.resources.is_empty() {
+            write!(f, "\nResources:")?;
+            for resource in self.resources.iter() {
+                write!(f, "\n- {}", resource)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A random 17 character alphanumeric nonce, as recommended by EIP-4361.
+pub fn generate_nonce() -> String {
+    Alphanumeric.sample_string(&mut rand::thread_rng(), 17)
+}
+
+#[derive(Default)]
+struct Fields {
+    uri: Option<String>,
+    version: Option<String>,
+    chain_id: Option<String>,
+    nonce: Option<String>,
+    issued_at: Option<String>,
+    expiration_time: Option<String>,
+    not_before: Option<String>,
+    request_id: Option<String>,
+    resources: Vec<String>,
+}
+
+fn required(value: Option<String>, tag: &str) -> Result<String, DimoError> {
+    value.ok_or_else(|| siwe_error(&format!("missing '{}'", tag)))
+}
+
+/// EIP-4361 nonces are at least 8 alphanumeric characters.
+fn parse_nonce(nonce: String) -> Result<String, DimoError> {
+    if nonce.len() < 8 || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(siwe_error(&format!(
+            "nonce '{}' is not at least 8 alphanumeric characters",
+            nonce
+        )));
+    }
+    Ok(nonce)
+}
+
+fn parse_time(value: &str) -> Result<SystemTime, DimoError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(SystemTime::from)
+        .map_err(|e| siwe_error(&format!("invalid timestamp '{}': {}", value, e)))
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// `https://Example.com:8080/callback` -> `example.com:8080`.
+fn authority(domain: &str) -> String {
+    let domain = domain.split_once("://").map_or(domain, |(_, rest)| rest);
+    let domain = domain.split(['/', '?', '#']).next().unwrap_or(domain);
+    domain.to_ascii_lowercase()
+}
+
+fn siwe_error(msg: &str) -> DimoError {
+    DimoError::Siwe(msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const ADDRESS: &str = "0x008AeEda4D805471dF9b2A5B0f38A0C3bCBA786b";
+
+    fn message(statement: Option<&str>) -> String {
+        let statement = statement.map(|s| format!("{}\n", s)).unwrap_or_default();
+        format!(
+            "https://example.com wants you to sign in with your Ethereum account:\n{}\n\n\
+             {}\nURI: https://example.com/login\nVersion: 1\nChain ID: 137\n\
+             Nonce: 32891756abcdEFGH\nIssued At: 2024-01-02T03:04:05.000Z\n\
+             Expiration Time: 2024-01-02T04:04:05.000Z\nResources:\n\
+             - ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/\n\
+             - https://example.com/my-web2-claim.json",
+            ADDRESS, statement
+        )
+    }
+
+    #[test]
+    fn round_trips_a_message_with_a_statement() {
+        let text = message(Some("I accept the ExampleOrg Terms of Service."));
+        assert!(text.contains(&format!("{}\n\nI accept", ADDRESS)));
+
+        let parsed = SiweMessage::parse(&text).unwrap();
+        assert_eq!(parsed.scheme.as_deref(), Some("https"));
+        assert_eq!(
+            parsed.statement.as_deref(),
+            Some("I accept the ExampleOrg Terms of Service.")
+        );
+        assert_eq!(parsed.chain_id, 137);
+        assert_eq!(parsed.resources.len(), 2);
+        assert_eq!(parsed.to_string(), text);
+    }
+
+    #[test]
+    fn round_trips_a_message_without_a_statement() {
+        let text = message(None);
+        assert!(text.contains(&format!("{}\n\n\nURI: ", ADDRESS)));
+
+        let parsed = SiweMessage::parse(&text).unwrap();
+        assert_eq!(parsed.statement, None);
+        assert_eq!(parsed.to_string(), text);
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        let built = SiweMessage::new("localhost:8080", ADDRESS, "http://localhost:8080", 1)
+            .unwrap()
+            .with_request_id("req-1")
+            .with_not_before(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert!(built
+            .to_string()
+            .contains(&format!("{}\n\n\nURI: ", ADDRESS)));
+
+        let mut parsed = SiweMessage::parse(&built.to_string()).unwrap();
+        // Display keeps milliseconds only.
+        parsed.issued_at = built.issued_at;
+        assert_eq!(parsed, built);
+    }
+
+    #[test]
+    fn rejects_short_or_non_alphanumeric_nonces() {
+        for nonce in ["abc123", "abcd-1234", "abcd 1234"] {
+            let text = message(None).replace("32891756abcdEFGH", nonce);
+            let err = SiweMessage::parse(&text).unwrap_err();
+            assert!(err.to_string().contains("nonce"), "{}", err);
+        }
+        SiweMessage::parse(&message(None).replace("32891756abcdEFGH", "abcd1234")).unwrap();
+    }
+
+    /// Half an hour after the example message was issued, before it expires.
+    fn during_validity() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_166_200)
+    }
+
+    fn expecting() -> SiweValidation {
+        SiweValidation {
+            domain: Some(vec!["https://example.com/callback".to_string()]),
+            address: Some(ADDRESS.to_lowercase()),
+            chain_id: Some(137),
+            time: Some(during_validity()),
+        }
+    }
+
+    fn rejected(parsed: &SiweMessage, validation: &SiweValidation, reason: &str) {
+        match parsed.validate(validation) {
+            Err(DimoError::Siwe(message)) => assert!(message.contains(reason), "{}", message),
+            result => panic!("expected a SIWE error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validates_a_matching_message() {
+        let parsed = SiweMessage::parse(&message(None)).unwrap();
+        parsed.validate(&expecting()).unwrap();
+        parsed
+            .validate(&SiweValidation {
+                time: Some(during_validity()),
+                ..SiweValidation::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_another_domain_address_or_chain() {
+        let parsed = SiweMessage::parse(&message(None)).unwrap();
+        let foreign = SiweValidation {
+            domain: Some(vec![
+                "localhost:8080".to_string(),
+                "example.org".to_string(),
+            ]),
+            ..expecting()
+        };
+        rejected(&parsed, &foreign, "domain example.com is not one of");
+
+        let other_signer = SiweValidation {
+            address: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            ..expecting()
+        };
+        rejected(&parsed, &other_signer, "does not match");
+
+        let other_chain = SiweValidation {
+            chain_id: Some(1),
+            ..expecting()
+        };
+        rejected(&parsed, &other_chain, "chain id 137 does not match 1");
+    }
+
+    #[test]
+    fn rejects_expired_and_not_yet_valid_messages() {
+        let parsed = SiweMessage::parse(&message(None)).unwrap();
+        let later = SiweValidation {
+            time: Some(during_validity() + Duration::from_secs(3600)),
+            ..expecting()
+        };
+        rejected(&parsed, &later, "expired");
+
+        let not_yet = parsed.with_not_before(during_validity() + Duration::from_secs(60));
+        rejected(&not_yet, &expecting(), "not valid yet");
+    }
+}
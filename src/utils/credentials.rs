@@ -1,49 +1,511 @@
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
 use std::error::Error;
-use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum CredentialsError {
     MissingCredentials,
     MissingFields(Vec<&'static str>),
+    /// A credentials file exists but could not be read or parsed.
+    InvalidFile {
+        path: PathBuf,
+        message: String,
+    },
 }
 
+impl fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialsError::MissingCredentials => write!(f, "no credentials found"),
+            CredentialsError::MissingFields(fields) => {
+                write!(f, "missing required fields: {}", fields.join(", "))
+            }
+            CredentialsError::InvalidFile { path, message } => {
+                write!(
+                    f,
+                    "invalid credentials file {}: {}",
+                    path.display(),
+                    message
+                )
+            }
+        }
+    }
+}
+
+impl Error for CredentialsError {}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Credentials {
     pub client_id: String,
-    pub private_key: String,
+    /// Only needed when no [`crate::signer::Signer`] is configured.
+    pub private_key: Option<String>,
     pub domain: String,
     pub developer_jwt: String,
     pub vehicle_jwt: String,
 }
 
-pub fn get_credentials() -> Result<Credentials, Box<dyn Error>> {
-    let client_id = env::var("CLIENT_ID").unwrap_or_default();
-    let private_key = env::var("API_KEY").unwrap_or_default();
-    let domain = env::var("REDIRECT_URI").unwrap_or_default();
-    let developer_jwt = env::var("DEVELOPER_JWT").ok().unwrap_or_default();
-    let vehicle_jwt = env::var("VEHICLE_JWT").ok().unwrap_or_default();
+impl Credentials {
+    /// In-memory credentials for the developer license `client_id` and its redirect URI.
+    pub fn new(client_id: &str, domain: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            private_key: None,
+            domain: domain.to_string(),
+            developer_jwt: String::new(),
+            vehicle_jwt: String::new(),
+        }
+    }
+
+    pub fn with_private_key(mut self, private_key: &str) -> Self {
+        self.private_key = Some(private_key.to_string());
+        self
+    }
+}
+
+/// A source of [`Credentials`].
+///
+/// A provider that has no credentials at all returns
+/// [`CredentialsError::MissingCredentials`], so a [`CredentialsChain`] moves on to the next one.
+pub trait CredentialsProvider: Send + Sync {
+    fn credentials(&self) -> Result<Credentials, CredentialsError>;
+}
+
+impl CredentialsProvider for Credentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        CredentialFields {
+            client_id: Some(self.client_id.clone()),
+            private_key: self.private_key.clone(),
+            domain: Some(self.domain.clone()),
+            developer_jwt: Some(self.developer_jwt.clone()),
+            vehicle_jwt: Some(self.vehicle_jwt.clone()),
+        }
+        .into_credentials()
+    }
+}
+
+impl<P: CredentialsProvider + ?Sized> CredentialsProvider for Arc<P> {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        (**self).credentials()
+    }
+}
+
+/// Reads the `CLIENT_ID`, `API_KEY`, `REDIRECT_URI`, `DEVELOPER_JWT` and `VEHICLE_JWT`
+/// environment variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvCredentials;
+
+impl CredentialsProvider for EnvCredentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        CredentialFields::from_vars(|name| env::var(name).ok()).into_credentials()
+    }
+}
+
+/// Reads the same variables as [`EnvCredentials`] from a `.env` file.
+#[derive(Debug, Clone)]
+pub struct DotenvCredentials {
+    path: PathBuf,
+}
+
+impl DotenvCredentials {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
 
-    let mut missing_fields = Vec::new();
-    if client_id.is_empty() {
-        missing_fields.push("client_id");
+impl Default for DotenvCredentials {
+    /// `.env` in the current directory.
+    fn default() -> Self {
+        Self::new(".env")
     }
-    if private_key.is_empty() {
-        missing_fields.push("api_key");
+}
+
+impl CredentialsProvider for DotenvCredentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let Some(contents) = read_optional(&self.path)? else {
+            return Err(CredentialsError::MissingCredentials);
+        };
+        let vars = parse_dotenv(&contents);
+        CredentialFields::from_vars(|name| vars.get(name).cloned()).into_credentials()
     }
-    if domain.is_empty() {
-        missing_fields.push("redirect_uri");
+}
+
+/// Reads a named profile from a TOML or JSON file, `~/.dimo/credentials` by default:
+///
+/// ```toml
+/// [default]
+/// client_id = "0x..."
+/// redirect_uri = "https://example.com/callback"
+/// api_key = "0x..."
+///
+/// [staging]
+/// client_id = "0x..."
+/// redirect_uri = "http://localhost:8080"
+/// ```
+///
+/// Files ending in `.json` are read as a JSON object of profiles.
+#[derive(Debug, Clone)]
+pub struct ProfileCredentials {
+    path: Option<PathBuf>,
+    profile: String,
+}
+
+impl ProfileCredentials {
+    pub fn new(path: impl Into<PathBuf>, profile: &str) -> Self {
+        Self {
+            path: Some(path.into()),
+            profile: profile.to_string(),
+        }
+    }
+
+    /// The `profile` section of `~/.dimo/credentials`.
+    pub fn profile(profile: &str) -> Self {
+        Self {
+            path: default_profile_path(),
+            profile: profile.to_string(),
+        }
     }
+}
 
-    if !missing_fields.is_empty() {
-        return Err(format!("Missing required fields: {:?}", missing_fields).into());
+impl Default for ProfileCredentials {
+    /// The profile named by `DIMO_PROFILE`, or `default`, in `~/.dimo/credentials`.
+    fn default() -> Self {
+        let profile = env::var("DIMO_PROFILE").unwrap_or_else(|_| "default".to_string());
+        Self::profile(&profile)
     }
+}
+
+impl CredentialsProvider for ProfileCredentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let Some(ref path) = self.path else {
+            return Err(CredentialsError::MissingCredentials);
+        };
+        let Some(contents) = read_optional(path)? else {
+            return Err(CredentialsError::MissingCredentials);
+        };
 
-    Ok(Credentials {
-        client_id,
-        private_key,
-        domain,
-        developer_jwt,
-        vehicle_jwt,
-    })
+        let invalid = |message: String| CredentialsError::InvalidFile {
+            path: path.clone(),
+            message,
+        };
+        let mut profiles: HashMap<String, CredentialFields> =
+            if path.extension().is_some_and(|ext| ext == "json") {
+                serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))?
+            } else {
+                toml::from_str(&contents).map_err(|e| invalid(e.to_string()))?
+            };
+
+        profiles
+            .remove(&self.profile)
+            .ok_or(CredentialsError::MissingCredentials)?
+            .into_credentials()
+    }
+}
+
+/// Asks each provider in turn and returns the first complete credentials.
+///
+/// When no provider has complete credentials, the first [`CredentialsError::MissingFields`]
+/// error is returned, so a half-configured source is reported rather than skipped silently.
+/// Unreadable files fail the chain straight away.
+#[derive(Clone, Default)]
+pub struct CredentialsChain {
+    providers: Vec<Arc<dyn CredentialsProvider>>,
+}
+
+impl CredentialsChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Environment variables, then `.env`, then the default profile of `~/.dimo/credentials`.
+    pub fn default_chain() -> Self {
+        Self::new()
+            .with_provider(EnvCredentials)
+            .with_provider(DotenvCredentials::default())
+            .with_provider(ProfileCredentials::default())
+    }
+
+    /// Appends `provider`, asked after the ones already in the chain.
+    pub fn with_provider<P: CredentialsProvider + 'static>(mut self, provider: P) -> Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+}
+
+impl CredentialsProvider for CredentialsChain {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let mut first_error = None;
+        for provider in self.providers.iter() {
+            match provider.credentials() {
+                Ok(credentials) => return Ok(credentials),
+                Err(CredentialsError::MissingCredentials) => {}
+                Err(err @ CredentialsError::MissingFields(_)) => {
+                    first_error.get_or_insert(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(first_error.unwrap_or(CredentialsError::MissingCredentials))
+    }
+}
+
+/// Reads the credentials from the environment variables, see [`EnvCredentials`].
+pub fn get_credentials() -> Result<Credentials, CredentialsError> {
+    EnvCredentials.credentials()
+}
+
+/// Credentials as written in a source, before the required fields are checked.
+#[derive(Debug, Default, Deserialize)]
+struct CredentialFields {
+    client_id: Option<String>,
+    #[serde(alias = "api_key")]
+    private_key: Option<String>,
+    #[serde(alias = "redirect_uri")]
+    domain: Option<String>,
+    developer_jwt: Option<String>,
+    vehicle_jwt: Option<String>,
+}
+
+impl CredentialFields {
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            client_id: var("CLIENT_ID"),
+            private_key: var("API_KEY"),
+            domain: var("REDIRECT_URI"),
+            developer_jwt: var("DEVELOPER_JWT"),
+            vehicle_jwt: var("VEHICLE_JWT"),
+        }
+    }
+
+    fn into_credentials(self) -> Result<Credentials, CredentialsError> {
+        let client_id = self.client_id.unwrap_or_default();
+        let private_key = self.private_key.filter(|key| !key.is_empty());
+        let domain = self.domain.unwrap_or_default();
+        let developer_jwt = self.developer_jwt.unwrap_or_default();
+        let vehicle_jwt = self.vehicle_jwt.unwrap_or_default();
+
+        let mut missing_fields = Vec::new();
+        if client_id.is_empty() {
+            missing_fields.push("client_id");
+        }
+        if domain.is_empty() {
+            missing_fields.push("redirect_uri");
+        }
+
+        if missing_fields.len() == 2 {
+            return Err(CredentialsError::MissingCredentials);
+        }
+        if !missing_fields.is_empty() {
+            return Err(CredentialsError::MissingFields(missing_fields));
+        }
+
+        Ok(Credentials {
+            client_id,
+            private_key,
+            domain,
+            developer_jwt,
+            vehicle_jwt,
+        })
+    }
+}
+
+/// The contents of `path`, or `None` when it does not exist.
+fn read_optional(path: &Path) -> Result<Option<String>, CredentialsError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(CredentialsError::InvalidFile {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        }),
+    }
+}
+
+/// Parses `KEY=value` lines, skipping blank lines and `#` comments, and accepting an
+/// `export ` prefix and single or double quoted values.
+fn parse_dotenv(contents: &str) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim();
+        let quoted = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].find(quote).map(|end| &value[1..end + 1]),
+            _ => None,
+        };
+        // An unquoted value ends at an inline comment.
+        let value = quoted.unwrap_or_else(|| value.split(" #").next().unwrap_or(value).trim_end());
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    vars
+}
+
+fn default_profile_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".dimo").join("credentials"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(suffix: &str, contents: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    /// A provider that always fails with `error`.
+    struct Failing(fn() -> CredentialsError);
+
+    impl CredentialsProvider for Failing {
+        fn credentials(&self) -> Result<Credentials, CredentialsError> {
+            Err((self.0)())
+        }
+    }
+
+    #[test]
+    fn parses_dotenv_syntax() {
+        let vars = parse_dotenv(
+            "# a comment\n\
+             \n\
+             CLIENT_ID=0xabc\n\
+             export REDIRECT_URI = https://example.com/callback # inline\n\
+             API_KEY=\"0x12 # not a comment\" # a comment\n\
+             DEVELOPER_JWT='single quoted'\n\
+             VEHICLE_JWT=value#with-hash\n\
+             EMPTY=\n\
+             not a pair\n",
+        );
+
+        assert_eq!(vars["CLIENT_ID"], "0xabc");
+        assert_eq!(vars["REDIRECT_URI"], "https://example.com/callback");
+        assert_eq!(vars["API_KEY"], "0x12 # not a comment");
+        assert_eq!(vars["DEVELOPER_JWT"], "single quoted");
+        assert_eq!(vars["VEHICLE_JWT"], "value#with-hash");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(vars.len(), 6);
+    }
+
+    #[test]
+    fn reads_a_dotenv_file() {
+        let env = file(
+            ".env",
+            "CLIENT_ID=0xabc\nREDIRECT_URI=http://localhost:8080\n",
+        );
+        let credentials = DotenvCredentials::new(env.path()).credentials().unwrap();
+        assert_eq!(credentials.client_id, "0xabc");
+        assert_eq!(credentials.domain, "http://localhost:8080");
+        assert_eq!(credentials.private_key, None);
+
+        let partial = file(".env", "CLIENT_ID=0xabc\nAPI_KEY=\n");
+        assert!(matches!(
+            DotenvCredentials::new(partial.path()).credentials(),
+            Err(CredentialsError::MissingFields(fields)) if fields == ["redirect_uri"]
+        ));
+        assert!(matches!(
+            DotenvCredentials::new(env.path().with_extension("missing")).credentials(),
+            Err(CredentialsError::MissingCredentials)
+        ));
+    }
+
+    #[test]
+    fn reads_toml_and_json_profiles() {
+        let toml = file(
+            "",
+            "[default]\nclient_id = \"0xabc\"\nredirect_uri = \"https://example.com\"\n\
+             api_key = \"0x12\"\n\n[staging]\nclient_id = \"0xdef\"\n",
+        );
+        let credentials = ProfileCredentials::new(toml.path(), "default")
+            .credentials()
+            .unwrap();
+        assert_eq!(credentials.client_id, "0xabc");
+        assert_eq!(credentials.private_key.as_deref(), Some("0x12"));
+        assert!(matches!(
+            ProfileCredentials::new(toml.path(), "staging").credentials(),
+            Err(CredentialsError::MissingFields(fields)) if fields == ["redirect_uri"]
+        ));
+        assert!(matches!(
+            ProfileCredentials::new(toml.path(), "absent").credentials(),
+            Err(CredentialsError::MissingCredentials)
+        ));
+
+        let json = file(
+            ".json",
+            r#"{ "default": { "client_id": "0xabc", "domain": "http://localhost:8080" } }"#,
+        );
+        let credentials = ProfileCredentials::new(json.path(), "default")
+            .credentials()
+            .unwrap();
+        assert_eq!(credentials.domain, "http://localhost:8080");
+
+        let broken = file("", "[default\n");
+        assert!(matches!(
+            ProfileCredentials::new(broken.path(), "default").credentials(),
+            Err(CredentialsError::InvalidFile { .. })
+        ));
+    }
+
+    #[test]
+    fn chain_skips_missing_credentials_and_returns_the_first_complete_ones() {
+        let chain = CredentialsChain::new()
+            .with_provider(Failing(|| CredentialsError::MissingCredentials))
+            .with_provider(Failing(|| {
+                CredentialsError::MissingFields(vec!["client_id"])
+            }))
+            .with_provider(Credentials::new("0xabc", "http://localhost:8080"))
+            .with_provider(Credentials::new("0xdef", "http://localhost:8080"));
+
+        assert_eq!(chain.credentials().unwrap().client_id, "0xabc");
+    }
+
+    #[test]
+    fn chain_reports_the_first_missing_fields_when_nothing_is_complete() {
+        let chain = CredentialsChain::new()
+            .with_provider(Failing(|| CredentialsError::MissingCredentials))
+            .with_provider(Failing(|| {
+                CredentialsError::MissingFields(vec!["redirect_uri"])
+            }))
+            .with_provider(Failing(|| {
+                CredentialsError::MissingFields(vec!["client_id"])
+            }));
+        assert!(matches!(
+            chain.credentials(),
+            Err(CredentialsError::MissingFields(fields)) if fields == ["redirect_uri"]
+        ));
+
+        let empty =
+            CredentialsChain::new().with_provider(Failing(|| CredentialsError::MissingCredentials));
+        assert!(matches!(
+            empty.credentials(),
+            Err(CredentialsError::MissingCredentials)
+        ));
+    }
+
+    #[test]
+    fn chain_stops_at_an_unreadable_file() {
+        let broken = file("", "[default\n");
+        let chain = CredentialsChain::new()
+            .with_provider(ProfileCredentials::new(broken.path(), "default"))
+            .with_provider(Credentials::new("0xabc", "http://localhost:8080"));
+
+        assert!(matches!(
+            chain.credentials(),
+            Err(CredentialsError::InvalidFile { .. })
+        ));
+    }
 }
@@ -0,0 +1,236 @@
+use crate::graphql::GraphQLError;
+use crate::rest::tokenexchange::Privilege;
+use crate::utils::credentials::CredentialsError;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// Error type returned by every client in the SDK.
+///
+/// `DimoError` is `Send + Sync`, so results can be moved across `tokio::spawn`
+/// boundaries and matched on by variant.
+#[derive(Debug)]
+pub enum DimoError {
+    /// The request never produced an HTTP response (DNS, connect, TLS, timeout, ...).
+    Transport(Box<dyn Error + Send + Sync>),
+    /// The server answered with a non-success status code.
+    Http {
+        status: StatusCode,
+        path: String,
+        /// The response body, parsed as JSON when possible and kept as a string otherwise.
+        body: Option<Value>,
+    },
+    /// A request or response body could not be (de)serialized.
+    Json(serde_json::Error),
+    /// A GraphQL endpoint answered with a non-empty `errors` array.
+    GraphQL {
+        errors: Vec<GraphQLError>,
+        /// The partial data returned next to the errors, as sent by the server.
+        data: Option<Value>,
+    },
+    /// Required credentials are missing or invalid.
+    Credentials(CredentialsError),
+    /// A message or payload could not be signed.
+    Signing(String),
+    /// A signature was valid but made by `recovered` rather than the `expected` address.
+    SignerMismatch { expected: String, recovered: String },
+    /// A JSON-RPC call failed, with the node's error `code`, or without one when the
+    /// response could not be understood.
+    JsonRpc {
+        method: String,
+        code: Option<i64>,
+        message: String,
+    },
+    /// A Sign-In With Ethereum message is malformed or failed validation.
+    Siwe(String),
+    /// The SDK was called with an invalid configuration or argument.
+    Config(String),
+    /// A JWT could not be decoded or failed verification.
+    InvalidToken(String),
+    /// `address` is not a registered signer of the developer license `client_id`.
+    UnauthorizedSigner { client_id: String, address: String },
+    /// The vehicle JWT for `token_id` does not grant privileges the request needs.
+    MissingPrivileges {
+        token_id: i32,
+        missing: Vec<Privilege>,
+    },
+    /// The request still failed after `attempts` tries; `source` is the last error.
+    Retried {
+        attempts: u32,
+        source: Box<DimoError>,
+    },
+    /// A step of the web3 challenge flow failed.
+    Auth {
+        step: AuthStep,
+        source: Box<DimoError>,
+    },
+}
+
+/// The step of the web3 challenge flow that produced a [`DimoError::Auth`] error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthStep {
+    GenerateChallenge,
+    SignChallenge,
+    VerifyChallenge,
+    SubmitChallenge,
+    ParseToken,
+}
+
+impl fmt::Display for AuthStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let step = match self {
+            AuthStep::GenerateChallenge => "generating challenge",
+            AuthStep::SignChallenge => "signing challenge",
+            AuthStep::VerifyChallenge => "verifying challenge",
+            AuthStep::SubmitChallenge => "submitting challenge",
+            AuthStep::ParseToken => "parsing access token",
+        };
+        f.write_str(step)
+    }
+}
+
+impl DimoError {
+    /// Returns the HTTP status code if this is an [`DimoError::Http`] error,
+    /// looking through [`DimoError::Retried`] and [`DimoError::Auth`] wrappers.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            DimoError::Http { status, .. } => Some(*status),
+            DimoError::Retried { source, .. } | DimoError::Auth { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// The error underneath any [`DimoError::Retried`] and [`DimoError::Auth`] wrappers,
+    /// e.g. the [`DimoError::Http`] returned by the last attempt.
+    pub fn root(&self) -> &DimoError {
+        match self {
+            DimoError::Retried { source, .. } | DimoError::Auth { source, .. } => source.root(),
+            err => err,
+        }
+    }
+
+    /// Like [`DimoError::root`], but takes ownership of the error.
+    pub fn into_root(self) -> DimoError {
+        match self {
+            DimoError::Retried { source, .. } | DimoError::Auth { source, .. } => {
+                source.into_root()
+            }
+            err => err,
+        }
+    }
+
+    /// Wraps this error with the web3 challenge step that produced it.
+    pub(crate) fn during(self, step: AuthStep) -> Self {
+        DimoError::Auth {
+            step,
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for DimoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DimoError::Transport(err) => write!(f, "request error: {}", err),
+            DimoError::Http { status, path, body } => match body {
+                Some(body) => write!(f, "error {} from {}: {}", status, path, body),
+                None => write!(f, "error {} from {}", status, path),
+            },
+            DimoError::Json(err) => write!(f, "json error: {}", err),
+            DimoError::GraphQL { errors, .. } => {
+                let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+                write!(f, "graphql error: {}", messages.join("; "))
+            }
+            DimoError::Credentials(err) => write!(f, "credentials error: {}", err),
+            DimoError::Signing(msg) => write!(f, "signing error: {}", msg),
+            DimoError::SignerMismatch {
+                expected,
+                recovered,
+            } => write!(
+                f,
+                "signature was made by {}, expected {}",
+                recovered, expected
+            ),
+            DimoError::JsonRpc {
+                method,
+                code,
+                message,
+            } => match code {
+                Some(code) => write!(f, "{} failed with code {}: {}", method, code, message),
+                None => write!(f, "{} failed: {}", method, message),
+            },
+            DimoError::Siwe(msg) => write!(f, "invalid SIWE message: {}", msg),
+            DimoError::Config(msg) => write!(f, "configuration error: {}", msg),
+            DimoError::InvalidToken(msg) => write!(f, "invalid token: {}", msg),
+            DimoError::UnauthorizedSigner { client_id, address } => write!(
+                f,
+                "{} is not an authorized signer for client id {}; add it to the developer license in the DIMO console",
+                address, client_id
+            ),
+            DimoError::MissingPrivileges { token_id, missing } => {
+                let missing: Vec<String> = missing.iter().map(|p| p.to_string()).collect();
+                write!(
+                    f,
+                    "vehicle {} has not granted the privileges: {}",
+                    token_id,
+                    missing.join(", ")
+                )
+            }
+            DimoError::Retried { attempts, source } => {
+                write!(f, "{} (after {} attempts)", source, attempts)
+            }
+            DimoError::Auth { step, source } => write!(f, "error {}: {}", step, source),
+        }
+    }
+}
+
+impl Error for DimoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DimoError::Transport(err) => Some(err.as_ref()),
+            DimoError::Json(err) => Some(err),
+            DimoError::Credentials(err) => Some(err),
+            DimoError::Retried { source, .. } | DimoError::Auth { source, .. } => {
+                Some(source.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for DimoError {
+    fn from(err: reqwest::Error) -> Self {
+        DimoError::Transport(Box::new(err))
+    }
+}
+
+impl From<serde_json::Error> for DimoError {
+    fn from(err: serde_json::Error) -> Self {
+        DimoError::Json(err)
+    }
+}
+
+impl From<CredentialsError> for DimoError {
+    fn from(err: CredentialsError) -> Self {
+        DimoError::Credentials(err)
+    }
+}
+
+impl From<secp256k1::Error> for DimoError {
+    fn from(err: secp256k1::Error) -> Self {
+        DimoError::Signing(err.to_string())
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for DimoError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        DimoError::InvalidToken(err.to_string())
+    }
+}
+
+impl From<hex::FromHexError> for DimoError {
+    fn from(err: hex::FromHexError) -> Self {
+        DimoError::Signing(err.to_string())
+    }
+}
@@ -1,4 +1,11 @@
 pub mod credentials;
+pub mod error;
+pub mod rate_limit;
 pub mod request;
+pub mod retry;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod transport;
 
 pub use credentials::get_credentials;
+pub use error::{AuthStep, DimoError};
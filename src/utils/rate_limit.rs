@@ -0,0 +1,182 @@
+use crate::utils::DimoError;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A token-bucket budget: `requests_per_second` sustained, with bursts of up to `burst`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allows `requests` per second with a burst of the same size.
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            requests_per_second: requests as f64,
+            burst: requests.max(1),
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<(), DimoError> {
+        if !(self.requests_per_second.is_finite() && self.requests_per_second > 0.0) {
+            return Err(DimoError::Config(format!(
+                "rate limit for {} must allow a positive number of requests per second",
+                name
+            )));
+        }
+        if self.burst == 0 {
+            return Err(DimoError::Config(format!(
+                "rate limit burst for {} must be at least 1",
+                name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Optional client-side rate limits, one per API host in
+/// [`crate::environments::dimo_environment::Routes`].
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    pub attestation: Option<RateLimit>,
+    pub auth: Option<RateLimit>,
+    pub identity: Option<RateLimit>,
+    pub devices: Option<RateLimit>,
+    pub device_definitions: Option<RateLimit>,
+    pub telemetry: Option<RateLimit>,
+    pub token_exchange: Option<RateLimit>,
+    pub trips: Option<RateLimit>,
+    pub valuations: Option<RateLimit>,
+}
+
+impl RateLimits {
+    pub fn validate(&self) -> Result<(), DimoError> {
+        let limits = [
+            ("attestation", self.attestation),
+            ("auth", self.auth),
+            ("identity", self.identity),
+            ("devices", self.devices),
+            ("device_definitions", self.device_definitions),
+            ("telemetry", self.telemetry),
+            ("token_exchange", self.token_exchange),
+            ("trips", self.trips),
+            ("valuations", self.valuations),
+        ];
+        for (name, limit) in limits {
+            if let Some(limit) = limit {
+                limit.validate(name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Token-bucket limiter shared by every clone of the sub-client it belongs to.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    ///
+    /// Each caller reserves a token up front, so concurrent callers are spaced out in
+    /// arrival order instead of waking up together.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.limit.requests_per_second)
+                .min(self.limit.burst as f64);
+            bucket.refilled_at = now;
+            bucket.tokens -= 1.0;
+
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-bucket.tokens / self.limit.requests_per_second)
+        };
+
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn spends_the_burst_then_paces_requests() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: 2.0,
+            burst: 2,
+        });
+        let started = Instant::now();
+
+        let mut sent_at = Vec::new();
+        for _ in 0..5 {
+            limiter.acquire().await;
+            sent_at.push(started.elapsed().as_millis());
+        }
+        assert_eq!(sent_at, [0, 0, 500, 1000, 1500]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spaces_out_concurrent_callers() {
+        let limiter = RateLimiter::new(RateLimit::per_second(1));
+        let started = Instant::now();
+
+        let wait = || async {
+            limiter.acquire().await;
+            started.elapsed().as_millis()
+        };
+        let sent_at = tokio::join!(wait(), wait(), wait());
+        assert_eq!(sent_at, (0, 1000, 2000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refills_while_idle() {
+        let limiter = RateLimiter::new(RateLimit::per_second(1));
+        limiter.acquire().await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+
+        let started = Instant::now();
+        limiter.acquire().await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+        limiter.acquire().await;
+        assert_eq!(started.elapsed(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn rejects_invalid_limits() {
+        let limits = RateLimits {
+            telemetry: Some(RateLimit {
+                requests_per_second: 0.0,
+                burst: 1,
+            }),
+            ..RateLimits::default()
+        };
+        let err = limits.validate().unwrap_err();
+        assert!(err.to_string().contains("telemetry"));
+    }
+}
@@ -1,8 +1,17 @@
-use crate::utils::get_credentials;
-use reqwest::{Client, Method};
+use crate::rest::auth::TokenManager;
+use crate::rest::tokenexchange::{Privilege, VehicleTokenStore};
+use crate::utils::rate_limit::{RateLimit, RateLimiter, RateLimits};
+use crate::utils::retry::RetryPolicy;
+use crate::utils::transport::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+use crate::utils::DimoError;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Certificate, Client, Method, Proxy, StatusCode, Url};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(serde::Serialize)]
 pub enum QueryValue {
@@ -16,9 +25,12 @@ pub enum BodyValue {
     Array(Vec<String>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RequestParams {
     pub method: Method,
+    /// Whether sending the request twice has the same effect as sending it once, so that
+    /// it may be retried under the client's [`RetryPolicy`].
+    pub idempotent: bool,
     pub base_url: String,
     pub path: String,
     pub query_params: Option<HashMap<String, String>>,
@@ -26,101 +38,729 @@ pub struct RequestParams {
     pub headers: Option<HashMap<String, String>>,
 }
 
+#[derive(Debug, Clone)]
 pub struct AuthRequestParams {
     pub method: Method,
+    /// See [`RequestParams::idempotent`].
+    pub idempotent: bool,
     pub base_url: String,
     pub path: String,
     pub query_params: Option<HashMap<String, String>>,
     pub body: Option<HashMap<String, Value>>,
     pub headers: Option<HashMap<String, String>>,
-    pub token_type: String,
+    pub token_type: TokenType,
 }
 
-/// Builds the authorization header using the appropriate token from `get_credentials`.
-/// `token_type` should be either "developer" (for developer jwt) or "vehicle" (for vehicle jwt).
-fn build_auth_header(token_type: &str) -> Result<HashMap<String, String>, Box<dyn Error>> {
-    let credentials = get_credentials()?;
+/// The bearer token attached to an [`AuthRequestParams`] request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenType {
+    /// The developer JWT from [`TokenManager`].
+    Developer,
+    /// A vehicle JWT from [`VehicleTokenStore`] for the given vehicle token ID, carrying
+    /// the privileges the request needs.
+    Vehicle {
+        token_id: i32,
+        privileges: Vec<Privilege>,
+    },
+}
 
-    let token = match token_type {
-        "developer" => &credentials.developer_jwt,
-        "vehicle" => &credentials.vehicle_jwt,
-        _ => return Err("Invalid token type specified. Use 'developer' or 'vehicle'.".into()),
-    };
+impl TokenType {
+    /// A vehicle token type for `token_id` as it appears in API paths.
+    pub fn vehicle(token_id: &str, privileges: &[Privilege]) -> Result<Self, DimoError> {
+        let token_id = token_id
+            .parse()
+            .map_err(|_| DimoError::Config(format!("invalid vehicle token id '{}'", token_id)))?;
+        Ok(TokenType::Vehicle {
+            token_id,
+            privileges: privileges.to_vec(),
+        })
+    }
+}
 
-    let mut headers = HashMap::new();
-    headers.insert("Authorization".to_string(), format!("Bearer {}", token));
-    Ok(headers)
+/// A response together with how many times the request was sent to get it, as returned by
+/// [`HttpClient::make_request_attempted`]. Failures report the count through
+/// [`DimoError::Retried`] instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempted<T> {
+    pub value: T,
+    /// `1` unless the request was retried.
+    pub attempts: u32,
 }
 
-pub async fn make_auth_request(params: AuthRequestParams) -> Result<Value, Box<dyn Error>> {
-    let auth_header = build_auth_header(&params.token_type)?;
-    let mut headers = params.headers.unwrap_or_default();
-    headers.extend(auth_header);
+/// Serializes a request model into the JSON object sent as a request body.
+pub(crate) fn to_body<T: serde::Serialize>(
+    request: &T,
+) -> Result<HashMap<String, Value>, DimoError> {
+    match serde_json::to_value(request)? {
+        Value::Object(body) => Ok(body.into_iter().collect()),
+        other => Err(DimoError::Config(format!(
+            "request body must be a JSON object, got {}",
+            other
+        ))),
+    }
+}
 
-    let request_params = RequestParams {
-        method: params.method,
-        base_url: params.base_url,
-        path: params.path,
-        query_params: params.query_params,
-        body: params.body,
-        headers: Some(headers),
-    };
+/// Settings for the HTTP client shared by every sub-client of [`crate::DIMO`].
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub connect_timeout: Option<Duration>,
+    pub read_timeout: Option<Duration>,
+    /// Maximum number of idle connections kept per host. `None` keeps reqwest's default.
+    pub pool_max_idle_per_host: Option<usize>,
+    pub user_agent: String,
+    /// Proxy URL used for every request, e.g. `http://proxy.internal:3128`.
+    pub proxy: Option<String>,
+    /// PEM encoded root certificate(s) trusted in addition to the system roots.
+    pub root_certificate: Option<Vec<u8>>,
+    pub retry: RetryPolicy,
+    pub rate_limits: RateLimits,
+}
 
-    make_request(request_params).await
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(30)),
+            pool_max_idle_per_host: None,
+            user_agent: concat!("dimo-rust-sdk/", env!("CARGO_PKG_VERSION")).to_string(),
+            proxy: None,
+            root_certificate: None,
+            retry: RetryPolicy::default(),
+            rate_limits: RateLimits::default(),
+        }
+    }
 }
 
-pub async fn make_request(params: RequestParams) -> Result<Value, Box<dyn Error>> {
-    let client = Client::new();
-    let url = format!("{}{}", params.base_url, params.path);
+impl ClientConfig {
+    fn build(&self) -> Result<Client, DimoError> {
+        self.rate_limits.validate()?;
+        self.retry.validate()?;
+        let mut builder = Client::builder().user_agent(&self.user_agent);
 
-    let mut request_builder = match params.method {
-        Method::GET => client.get(&url),
-        Method::POST => client.post(&url),
-        Method::PATCH => client.patch(&url),
-        _ => return Err("Unsupported method".into()),
-    };
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(ref proxy) = self.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|e| DimoError::Config(format!("invalid proxy '{}': {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(ref pem) = self.root_certificate {
+            let certificates = Certificate::from_pem_bundle(pem)
+                .map_err(|e| DimoError::Config(format!("invalid root certificate: {}", e)))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
 
-    if let Some(ref query_params) = params.query_params {
-        request_builder = request_builder.query(&query_params);
-    };
+        builder
+            .build()
+            .map_err(|e| DimoError::Config(format!("could not build http client: {}", e)))
+    }
+}
 
-    let use_form_body = params
-        .headers
-        .as_ref()
-        .and_then(|headers| headers.get("Content-Type"))
-        .map(|value| value.eq_ignore_ascii_case("x-www-form-urlencoded"))
-        .unwrap_or(false);
-
-    if let Some(body) = params.body {
-        request_builder = if use_form_body {
-            request_builder.form(&body)
-        } else {
-            request_builder.json(&body)
-        };
+/// HTTP client shared by every sub-client of [`crate::DIMO`].
+///
+/// Cloning is cheap and clones share the same transport and connection pool.
+#[derive(Clone)]
+pub struct HttpClient {
+    transport: Arc<dyn HttpTransport>,
+    retry: RetryPolicy,
+    rate_limits: RateLimits,
+    limiter: Option<Arc<RateLimiter>>,
+    tokens: Option<Arc<TokenManager>>,
+    vehicle_tokens: Option<Arc<VehicleTokenStore>>,
+}
+
+impl HttpClient {
+    pub fn new(config: &ClientConfig) -> Result<Self, DimoError> {
+        Self::from_client(config.build()?)
+            .with_retry_policy(config.retry.clone())
+            .with_rate_limits(config.rate_limits.clone())
+    }
+
+    /// Wraps an already configured `reqwest::Client`.
+    pub fn from_client(client: Client) -> Self {
+        Self::with_transport(Arc::new(ReqwestTransport::new(client)))
     }
 
-    if let Some(headers) = params.headers {
-        for (key, value) in headers {
-            request_builder = request_builder.header(&key, &value);
+    /// Sends every request through `transport` instead of reqwest.
+    pub fn with_transport(transport: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            transport,
+            retry: RetryPolicy::default(),
+            rate_limits: RateLimits::default(),
+            limiter: None,
+            tokens: None,
+            vehicle_tokens: None,
         }
     }
 
-    let response_result = request_builder.send().await;
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
 
-    match response_result {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                let json_response = resp.json::<Value>().await?;
-                Ok(json_response)
-            } else {
-                let status = resp.status();
-                let error_text = resp
-                    .text()
+    /// Sets the per-host rate limits applied by [`crate::DIMO`] to each sub-client.
+    pub fn with_rate_limits(mut self, rate_limits: RateLimits) -> Result<Self, DimoError> {
+        rate_limits.validate()?;
+        self.rate_limits = rate_limits;
+        Ok(self)
+    }
+
+    /// Uses `tokens` as the source of the developer JWT for authenticated requests.
+    pub fn with_token_manager(mut self, tokens: Arc<TokenManager>) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Uses `vehicle_tokens` as the source of vehicle JWTs for authenticated requests.
+    pub fn with_vehicle_token_store(mut self, vehicle_tokens: Arc<VehicleTokenStore>) -> Self {
+        self.vehicle_tokens = Some(vehicle_tokens);
+        self
+    }
+
+    pub(crate) fn rate_limits(&self) -> &RateLimits {
+        &self.rate_limits
+    }
+
+    /// A clone of this client whose requests wait on their own limiter for `limit`.
+    pub(crate) fn limited(&self, limit: Option<RateLimit>) -> Self {
+        let mut client = self.clone();
+        client.limiter = limit.map(|limit| Arc::new(RateLimiter::new(limit)));
+        client
+    }
+
+    pub async fn make_auth_request(&self, params: AuthRequestParams) -> Result<Value, DimoError> {
+        self.make_auth_request_attempted(params)
+            .await
+            .map(|response| response.value)
+    }
+
+    /// Like [`HttpClient::make_auth_request`], but also returns how many times the request
+    /// was sent, counting a send rejected with `401` before the token was refreshed.
+    pub async fn make_auth_request_attempted(
+        &self,
+        params: AuthRequestParams,
+    ) -> Result<Attempted<Value>, DimoError> {
+        let token = self.bearer_token(&params.token_type).await?;
+        let result = self.send_with_token(&params, &token).await;
+
+        match result {
+            Err(err) if err.status() == Some(StatusCode::UNAUTHORIZED) => {
+                // The cached token was rejected: drop it and retry once with a fresh one.
+                self.invalidate(&params.token_type, &token).await;
+                let token = self.bearer_token(&params.token_type).await?;
+                let mut response = self.send_with_token(&params, &token).await?;
+                response.attempts += 1;
+                Ok(response)
+            }
+            result => result,
+        }
+    }
+
+    /// Boxed because fetching a vehicle JWT sends a developer-authenticated token exchange
+    /// request, which makes this future recursive.
+    fn bearer_token<'a>(
+        &'a self,
+        token_type: &'a TokenType,
+    ) -> Pin<Box<dyn Future<Output = Result<String, DimoError>> + Send + 'a>> {
+        Box::pin(async move {
+            match token_type {
+                TokenType::Developer => match self.tokens {
+                    Some(ref tokens) => tokens.token().await,
+                    None => Err(DimoError::Config(
+                        "no token manager configured for developer requests".to_string(),
+                    )),
+                },
+                TokenType::Vehicle {
+                    token_id,
+                    privileges,
+                } => match self.vehicle_tokens {
+                    Some(ref vehicle_tokens) => vehicle_tokens
+                        .token(*token_id, privileges)
+                        .await
+                        .map(|token| token.token().to_string()),
+                    None => Err(DimoError::Config(
+                        "no vehicle token store configured for vehicle requests".to_string(),
+                    )),
+                },
+            }
+        })
+    }
+
+    async fn invalidate(&self, token_type: &TokenType, rejected: &str) {
+        match (token_type, &self.tokens, &self.vehicle_tokens) {
+            (TokenType::Developer, Some(tokens), _) => tokens.invalidate(rejected).await,
+            (
+                TokenType::Vehicle {
+                    token_id,
+                    privileges,
+                },
+                _,
+                Some(vehicle_tokens),
+            ) => {
+                vehicle_tokens
+                    .invalidate(*token_id, privileges, rejected)
                     .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                Err(format!("Error {}: {}", status, error_text).into())
             }
+            _ => {}
+        }
+    }
+
+    async fn send_with_token(
+        &self,
+        params: &AuthRequestParams,
+        token: &str,
+    ) -> Result<Attempted<Value>, DimoError> {
+        let mut headers = params.headers.clone().unwrap_or_default();
+        headers.insert("Authorization".to_string(), format!("Bearer {}", token));
+
+        let request_params = RequestParams {
+            method: params.method.clone(),
+            idempotent: params.idempotent,
+            base_url: params.base_url.clone(),
+            path: params.path.clone(),
+            query_params: params.query_params.clone(),
+            body: params.body.clone(),
+            headers: Some(headers),
+        };
+
+        self.make_request_attempted(request_params).await
+    }
+
+    pub async fn make_request(&self, params: RequestParams) -> Result<Value, DimoError> {
+        self.make_request_attempted(params)
+            .await
+            .map(|response| response.value)
+    }
+
+    /// Like [`HttpClient::make_request`], but also returns how many times the request was
+    /// sent under the client's [`RetryPolicy`].
+    pub async fn make_request_attempted(
+        &self,
+        params: RequestParams,
+    ) -> Result<Attempted<Value>, DimoError> {
+        self.send_attempted(params, parse_response).await
+    }
+
+    /// Like [`HttpClient::make_request`], but returns the successful response as sent by the
+    /// server, for endpoints that answer with something other than JSON, such as images.
+    pub async fn make_raw_request(&self, params: RequestParams) -> Result<HttpResponse, DimoError> {
+        self.send_attempted(params, check_status)
+            .await
+            .map(|response| response.value)
+    }
+
+    async fn send_attempted<T>(
+        &self,
+        params: RequestParams,
+        parse: fn(HttpResponse, &str) -> Result<T, DimoError>,
+    ) -> Result<Attempted<T>, DimoError> {
+        let request = build_request(&params)?;
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            if let Some(ref limiter) = self.limiter {
+                limiter.acquire().await;
+            }
+            let result = self.transport.send(request.clone()).await;
+
+            let retryable = match result {
+                Ok(ref response) => self.retry.is_retryable_status(response.status),
+                Err(DimoError::Transport(_)) => true,
+                Err(_) => false,
+            };
+            let delay = if retryable && params.idempotent && self.retry.allows(attempts) {
+                self.retry.delay(attempts, result.as_ref().ok())
+            } else {
+                None
+            };
+
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            let result = result.and_then(|response| parse(response, &params.path));
+            return match result {
+                Ok(value) => Ok(Attempted { value, attempts }),
+                Err(err) if attempts > 1 => Err(DimoError::Retried {
+                    attempts,
+                    source: Box::new(err),
+                }),
+                Err(err) => Err(err),
+            };
+        }
+    }
+}
+
+/// Turns a raw response into JSON, or into a [`DimoError::Http`] for non-success statuses.
+fn parse_response(response: HttpResponse, path: &str) -> Result<Value, DimoError> {
+    let response = check_status(response, path)?;
+    if response.body.is_empty() {
+        // e.g. `204 No Content` from DELETE endpoints
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_slice(&response.body)?)
+}
+
+/// Passes success responses through and turns the others into a [`DimoError::Http`].
+fn check_status(response: HttpResponse, path: &str) -> Result<HttpResponse, DimoError> {
+    if response.status.is_success() {
+        Ok(response)
+    } else {
+        Err(DimoError::Http {
+            status: response.status,
+            path: path.to_string(),
+            body: parse_error_body(&response.body),
+        })
+    }
+}
+
+/// Resolves `params` into the URL, headers and encoded body sent by the transport.
+fn build_request(params: &RequestParams) -> Result<HttpRequest, DimoError> {
+    let url = format!("{}{}", params.base_url, params.path);
+    let mut url =
+        Url::parse(&url).map_err(|e| DimoError::Config(format!("invalid url '{}': {}", url, e)))?;
+    match params.query_params {
+        Some(ref query_params) if !query_params.is_empty() => {
+            url.query_pairs_mut().extend_pairs(query_params);
+        }
+        _ => {}
+    }
+
+    let mut headers = HeaderMap::new();
+    let mut use_form_body = false;
+    for (key, value) in params.headers.iter().flatten() {
+        if key.eq_ignore_ascii_case("Content-Type")
+            && value.eq_ignore_ascii_case("x-www-form-urlencoded")
+        {
+            use_form_body = true;
+            continue;
+        }
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| DimoError::Config(format!("invalid header name '{}': {}", key, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| DimoError::Config(format!("invalid value for header '{}': {}", key, e)))?;
+        headers.insert(name, value);
+    }
+
+    let body = match params.body {
+        Some(ref body) if use_form_body => {
+            headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+            let encoded = serde_urlencoded::to_string(body)
+                .map_err(|e| DimoError::Config(format!("could not encode form body: {}", e)))?;
+            Some(encoded.into_bytes())
+        }
+        Some(ref body) => {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            Some(serde_json::to_vec(body)?)
+        }
+        None => None,
+    };
+
+    Ok(HttpRequest {
+        method: params.method.clone(),
+        url: url.to_string(),
+        headers,
+        body,
+    })
+}
+
+/// Parses an error response body as JSON, falling back to a plain string.
+fn parse_error_body(bytes: &[u8]) -> Option<Value> {
+    if bytes.is_empty() {
+        return None;
+    }
+    let body = serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()));
+    Some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest::devicedefinitions::DecodeVinRequest;
+    use crate::utils::testing::{self, bearer, fast_retries, ok};
+    use crate::utils::transport::MockTransport;
+    use reqwest::header::RETRY_AFTER;
+    use serde_json::json;
+
+    fn params(method: Method, idempotent: bool) -> RequestParams {
+        RequestParams {
+            method,
+            idempotent,
+            base_url: "https://api.example.com".to_string(),
+            path: "/v1/things".to_string(),
+            query_params: None,
+            body: None,
+            headers: None,
+        }
+    }
+
+    fn status(status: StatusCode) -> HttpResponse {
+        HttpResponse::new(status, "")
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests_until_they_succeed() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(status(StatusCode::SERVICE_UNAVAILABLE))
+            .push_error("connection reset")
+            .push(ok(json!({ "ok": true })));
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(fast_retries(3));
+
+        // GraphQL queries are POSTs, but idempotent.
+        let response = client
+            .make_request_attempted(params(Method::POST, true))
+            .await
+            .unwrap();
+        assert_eq!(response.value, json!({ "ok": true }));
+        assert_eq!(response.attempts, 3);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn never_retries_non_idempotent_requests() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(status(StatusCode::SERVICE_UNAVAILABLE))
+            .push(ok(json!({})));
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(fast_retries(3));
+
+        let err = client
+            .make_request(params(Method::POST, false))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DimoError::Http { status, .. } if status == StatusCode::SERVICE_UNAVAILABLE
+        ));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_the_attempts_and_last_error_when_retries_run_out() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .push(status(StatusCode::BAD_GATEWAY))
+            .push(status(StatusCode::GATEWAY_TIMEOUT))
+            .push(status(StatusCode::TOO_MANY_REQUESTS));
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(fast_retries(3));
+
+        let err = client
+            .make_request(params(Method::GET, true))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DimoError::Retried { attempts: 3, .. }));
+        assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert!(matches!(err.root(), DimoError::Http { path, .. } if path == "/v1/things"));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_statuses() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push(status(StatusCode::BAD_REQUEST));
+        let client =
+            HttpClient::with_transport(transport.clone()).with_retry_policy(fast_retries(3));
+
+        let err = client
+            .make_request(params(Method::GET, true))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_retry_after() {
+        let mut throttled = status(StatusCode::TOO_MANY_REQUESTS);
+        throttled
+            .headers
+            .insert(RETRY_AFTER, HeaderValue::from_static("5"));
+        let transport = Arc::new(MockTransport::new());
+        transport.push(throttled).push(ok(json!({})));
+        let client = HttpClient::with_transport(transport.clone());
+
+        let started = tokio::time::Instant::now();
+        client
+            .make_request(params(Method::GET, true))
+            .await
+            .unwrap();
+        assert_eq!(started.elapsed().as_secs(), 5);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_exponentially() {
+        let transport = Arc::new(MockTransport::new());
+        for _ in 0..3 {
+            transport.push(status(StatusCode::SERVICE_UNAVAILABLE));
         }
-        Err(err) => Err(format!("Request error: {}", err).into()),
+        transport.push(ok(json!({})));
+        let client = HttpClient::with_transport(transport.clone()).with_retry_policy(RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        });
+
+        let started = tokio::time::Instant::now();
+        client
+            .make_request(params(Method::GET, true))
+            .await
+            .unwrap();
+        // 1s + 2s + 4s
+        assert_eq!(started.elapsed().as_secs(), 7);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_retry_after_is_too_long() {
+        let mut throttled = status(StatusCode::TOO_MANY_REQUESTS);
+        throttled
+            .headers
+            .insert(RETRY_AFTER, HeaderValue::from_static("3600"));
+        let transport = Arc::new(MockTransport::new());
+        transport.push(throttled);
+        let client = HttpClient::with_transport(transport.clone());
+
+        let err = client
+            .make_request(params(Method::GET, true))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    /// Answers the signer check and the web3 challenge flow, issuing the token `DEV2`.
+    fn challenge_flow(transport: &MockTransport) {
+        transport
+            .on(
+                Method::POST,
+                "/query",
+                ok(json!({ "data": { "developerLicense": { "signers": {
+                    "nodes": [{ "address": testing::SIGNER_ADDRESS }],
+                    "pageInfo": { "hasNextPage": false, "endCursor": null },
+                } } } })),
+            )
+            .on(
+                Method::POST,
+                "/auth/web3/generate_challenge",
+                ok(json!({ "challenge": testing::challenge(), "state": "state" })),
+            )
+            .on(
+                Method::POST,
+                "/auth/web3/submit_challenge",
+                ok(json!({
+                    "access_token": "DEV2",
+                    "id_token": "id",
+                    "token_type": "bearer",
+                    "expires_in": 3600,
+                })),
+            );
+    }
+
+    #[tokio::test]
+    async fn refreshes_a_rejected_developer_token_once() {
+        let transport = Arc::new(MockTransport::new());
+        challenge_flow(&transport);
+        transport
+            .push(status(StatusCode::UNAUTHORIZED))
+            .push(ok(json!({ "deviceDefinitionId": "ford_bronco_2022" })));
+        let dimo = testing::dimo(&transport).await;
+
+        let decoded = dimo
+            .devicedefinitions
+            .decode_vin(&DecodeVinRequest::new("1FMEE5DH0NLA12345", "USA"))
+            .await
+            .unwrap();
+        assert_eq!(decoded.device_definition_id, "ford_bronco_2022");
+
+        let requests = transport.requests();
+        let decodes: Vec<&HttpRequest> = requests
+            .iter()
+            .filter(|r| r.url.ends_with("/decode-vin"))
+            .collect();
+        assert_eq!(decodes.len(), 2);
+        assert_eq!(bearer(decodes[0]), "Bearer DEV");
+        assert_eq!(bearer(decodes[1]), "Bearer DEV2");
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|r| r.url.ends_with("/submit_challenge"))
+                .count(),
+            1
+        );
+        assert_eq!(
+            dimo.tokens.current().await.unwrap().token.access_token(),
+            "DEV2"
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_the_second_401_when_the_fresh_token_is_rejected_too() {
+        let transport = Arc::new(MockTransport::new());
+        challenge_flow(&transport);
+        transport.on(
+            Method::POST,
+            "/device-definitions/decode-vin",
+            status(StatusCode::UNAUTHORIZED),
+        );
+        let dimo = testing::dimo(&transport).await;
+
+        let err = dimo
+            .devicedefinitions
+            .decode_vin(&DecodeVinRequest::new("1FMEE5DH0NLA12345", "USA"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+        let requests = transport.requests();
+        let count = |suffix: &str| requests.iter().filter(|r| r.url.ends_with(suffix)).count();
+        assert_eq!(count("/decode-vin"), 2);
+        assert_eq!(count("/submit_challenge"), 1);
+    }
+
+    #[tokio::test]
+    async fn counts_the_rejected_send_in_the_attempts() {
+        let transport = Arc::new(MockTransport::new());
+        challenge_flow(&transport);
+        transport
+            .push(status(StatusCode::UNAUTHORIZED))
+            .push(ok(json!({ "ok": true })));
+        let dimo = testing::dimo(&transport).await;
+        let client = HttpClient::with_transport(transport.clone())
+            .with_retry_policy(RetryPolicy::none())
+            .with_token_manager(dimo.tokens.clone());
+
+        let response = client
+            .make_auth_request_attempted(AuthRequestParams {
+                method: Method::GET,
+                idempotent: true,
+                base_url: "https://api.example.com".to_string(),
+                path: "/v1/things".to_string(),
+                query_params: None,
+                body: None,
+                headers: None,
+                token_type: TokenType::Developer,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.value, json!({ "ok": true }));
+        assert_eq!(response.attempts, 2);
     }
 }
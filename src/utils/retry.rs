@@ -0,0 +1,113 @@
+use crate::utils::transport::HttpResponse;
+use crate::utils::DimoError;
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use std::time::{Duration, SystemTime};
+
+/// Controls how failed requests are retried.
+///
+/// A request is retried when it fails with one of `retry_statuses` or with a transport
+/// error, but only if the SDK method that sent it is idempotent. Reads, including GraphQL
+/// queries sent with `POST`, are retried; commands such as `Devices::unlock_doors` never are.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on every following retry.
+    pub base_delay: Duration,
+    /// Upper bound for the computed backoff delay.
+    pub max_delay: Duration,
+    /// Fraction (`0.0..=1.0`) of each delay that is randomized.
+    pub jitter: f64,
+    /// Longest `Retry-After` the client is willing to wait. Longer waits are not retried.
+    pub max_retry_after: Duration,
+    pub retry_statuses: Vec<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+            jitter: 0.5,
+            max_retry_after: Duration::from_secs(60),
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends every request exactly once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), DimoError> {
+        if self.max_attempts == 0 {
+            return Err(DimoError::Config(
+                "retry policy must allow at least 1 attempt".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(DimoError::Config(format!(
+                "retry jitter must be between 0.0 and 1.0, got {}",
+                self.jitter
+            )));
+        }
+        if self.base_delay > self.max_delay {
+            return Err(DimoError::Config(
+                "retry base_delay must not exceed max_delay".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn allows(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    pub(crate) fn is_retryable_status(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status)
+    }
+
+    /// Delay before retry number `attempt` (1-based), honouring `Retry-After` when present.
+    /// Returns `None` when the server asks to wait longer than `max_retry_after`.
+    pub(crate) fn delay(&self, attempt: u32, response: Option<&HttpResponse>) -> Option<Duration> {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let backoff = backoff.mul_f64(1.0 - jitter * rand::thread_rng().gen::<f64>());
+
+        match response.and_then(retry_after) {
+            Some(wait) if wait > self.max_retry_after => None,
+            Some(wait) => Some(wait.max(backoff)),
+            None => Some(backoff),
+        }
+    }
+}
+
+/// Parses a `Retry-After` header given either in seconds or as an HTTP date.
+fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    let value = response.headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
@@ -0,0 +1,114 @@
+//! Fixtures shared by the unit tests.
+
+use crate::rest::auth::{CachedToken, MemoryTokenCache, TokenCache};
+use crate::signer::LocalSigner;
+use crate::utils::credentials::Credentials;
+use crate::utils::retry::RetryPolicy;
+use crate::utils::transport::{HttpRequest, HttpResponse, MockTransport};
+use crate::{Environment, DIMO};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// The private key of [`SIGNER_ADDRESS`].
+pub const PRIVATE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+pub const SIGNER_ADDRESS: &str = "0x008AeEda4D805471dF9b2A5B0f38A0C3bCBA786b";
+pub const CLIENT_ID: &str = "0xabc";
+pub const DOMAIN: &str = "http://localhost:8080";
+
+/// The challenge the auth server issues to [`SIGNER_ADDRESS`] for [`DOMAIN`].
+pub fn challenge() -> String {
+    format!(
+        "localhost:8080 wants you to sign in with your Ethereum account:\n{}\n\n\
+         localhost:8080 is asking you sign in.\n\nURI: {}\nVersion: 1\nChain ID: 80001\n\
+         Nonce: aBcD1234efgh5678\nIssued At: 2024-01-02T03:04:05Z",
+        SIGNER_ADDRESS, DOMAIN
+    )
+}
+
+pub fn signer() -> LocalSigner {
+    LocalSigner::from_hex(PRIVATE_KEY).unwrap()
+}
+
+pub fn ok(body: Value) -> HttpResponse {
+    HttpResponse::json(StatusCode::OK, &body)
+}
+
+/// A retry policy that retries immediately, so tests do not sleep.
+pub fn fast_retries(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    }
+}
+
+/// An unsigned JWT carrying `claims`, enough for the SDK's unverified decoding.
+pub fn jwt(claims: Value) -> String {
+    format!(
+        "{}.{}.signature",
+        URL_SAFE_NO_PAD.encode(json!({ "alg": "ES256", "typ": "JWT" }).to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    )
+}
+
+/// The token exchange response for a vehicle JWT granting `privileges` on `token_id`.
+pub fn vehicle_token_response(token_id: i32, privileges: &[i32]) -> HttpResponse {
+    ok(json!({
+        "token": jwt(json!({
+            "token_id": token_id.to_string(),
+            "contract_address": "0xbA5738a18d83D41847dfFbDC6101d37C69c9B0cF",
+            "privilege_ids": privileges,
+            "iss": "https://auth-roles-rights.dimo.zone",
+            "exp": 4102444800u64,
+        }))
+    }))
+}
+
+/// A [`DIMO`] client on `transport` whose developer JWT `DEV` is already cached.
+pub async fn dimo(transport: &Arc<MockTransport>) -> DIMO {
+    let cache = MemoryTokenCache::new();
+    cache
+        .put(CachedToken {
+            token: serde_json::from_value(json!({
+                "access_token": "DEV",
+                "id_token": "id",
+                "token_type": "bearer",
+                "expires_in": 3600,
+            }))
+            .unwrap(),
+            expires_at: SystemTime::now() + Duration::from_secs(3600),
+        })
+        .await;
+
+    DIMO::builder()
+        .environment(Environment::Dev)
+        .credentials(Credentials::new(CLIENT_ID, DOMAIN))
+        .signer(signer())
+        .transport(transport.clone())
+        .retry_policy(RetryPolicy::none())
+        .token_cache(cache)
+        .build()
+        .unwrap()
+}
+
+/// A JSON response body from `tests/fixtures`, e.g. `fixture("trips/trips.json")`.
+pub fn fixture(path: &str) -> Value {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path);
+    serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+}
+
+pub fn body(request: &HttpRequest) -> Value {
+    serde_json::from_slice(request.body.as_deref().unwrap()).unwrap()
+}
+
+pub fn bearer(request: &HttpRequest) -> &str {
+    request.headers["authorization"].to_str().unwrap()
+}
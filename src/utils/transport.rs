@@ -0,0 +1,218 @@
+use crate::utils::DimoError;
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, StatusCode};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// A fully resolved HTTP request handed to an [`HttpTransport`].
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
+/// The raw response returned by an [`HttpTransport`].
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// A response with a JSON body.
+    pub fn json(status: StatusCode, body: &Value) -> Self {
+        let mut response = Self::new(status, body.to_string());
+        response.headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            "application/json".parse().unwrap(),
+        );
+        response
+    }
+}
+
+/// Sends HTTP requests on behalf of the SDK.
+///
+/// [`ReqwestTransport`] is used by default. Implement this trait to route requests
+/// through another HTTP stack, or use [`MockTransport`] to run without a network.
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, DimoError>;
+}
+
+/// The default transport, backed by a `reqwest::Client`.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, DimoError> {
+        let mut builder = self
+            .client
+            .request(request.method, &request.url)
+            .headers(request.headers);
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        let resp = builder.send().await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.bytes().await?.to_vec();
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// An in-memory transport that records every request and replies with canned responses.
+///
+/// Responses registered with [`MockTransport::on`] are matched by method and URL path
+/// first; otherwise the queue filled by [`MockTransport::push`] is consumed in order.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    routes: Mutex<Vec<(Method, String, HttpResponse)>>,
+    queue: Mutex<VecDeque<Result<HttpResponse, String>>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Always answers requests with `method` whose URL path equals `path`.
+    pub fn on(&self, method: Method, path: &str, response: HttpResponse) -> &Self {
+        self.routes
+            .lock()
+            .unwrap()
+            .push((method, path.to_string(), response));
+        self
+    }
+
+    /// Queues a response for the next unmatched request.
+    pub fn push(&self, response: HttpResponse) -> &Self {
+        self.queue.lock().unwrap().push_back(Ok(response));
+        self
+    }
+
+    /// Queues a transport failure, as if the connection had been dropped.
+    pub fn push_error(&self, message: &str) -> &Self {
+        self.queue
+            .lock()
+            .unwrap()
+            .push_back(Err(message.to_string()));
+        self
+    }
+
+    /// Every request sent so far, oldest first.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MockTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, DimoError> {
+        let path = reqwest::Url::parse(&request.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+        let method = request.method.clone();
+        self.requests.lock().unwrap().push(request);
+
+        let routed = self
+            .routes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(m, p, _)| *m == method && *p == path)
+            .map(|(_, _, response)| response.clone());
+        if let Some(response) = routed {
+            return Ok(response);
+        }
+
+        match self.queue.lock().unwrap().pop_front() {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(message)) => Err(DimoError::Transport(message.into())),
+            None => Err(DimoError::Transport(
+                format!("no canned response for {} {}", method, path).into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: Method::GET,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_answers_routes_before_the_queue_and_records_requests() {
+        let transport = MockTransport::new();
+        transport.on(
+            Method::GET,
+            "/routed",
+            HttpResponse::new(StatusCode::OK, "routed"),
+        );
+        transport
+            .push(HttpResponse::new(StatusCode::ACCEPTED, "queued"))
+            .push_error("connection reset");
+
+        let routed = transport.send(get("http://api/routed?x=1")).await.unwrap();
+        assert_eq!(routed.body, b"routed");
+        let queued = transport.send(get("http://api/other")).await.unwrap();
+        assert_eq!(queued.status, StatusCode::ACCEPTED);
+        let routed = transport.send(get("http://api/routed")).await.unwrap();
+        assert_eq!(routed.body, b"routed");
+
+        let dropped = transport.send(get("http://api/other")).await.unwrap_err();
+        assert!(matches!(dropped, DimoError::Transport(_)));
+        let unmatched = transport.send(get("http://api/other")).await.unwrap_err();
+        assert!(unmatched
+            .to_string()
+            .contains("no canned response for GET /other"));
+
+        let urls: Vec<String> = transport.requests().into_iter().map(|r| r.url).collect();
+        assert_eq!(
+            urls,
+            [
+                "http://api/routed?x=1",
+                "http://api/other",
+                "http://api/routed",
+                "http://api/other",
+                "http://api/other",
+            ]
+        );
+    }
+}
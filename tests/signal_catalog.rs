@@ -0,0 +1,79 @@
+#[path = "../examples/generate_signals/catalog.rs"]
+mod catalog;
+
+use catalog::{CATALOG, SCHEMA};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn catalog_is_up_to_date_with_the_schema() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let schema = fs::read_to_string(root.join(SCHEMA)).unwrap();
+    let generated =
+        catalog::rustfmt(&catalog::generate(&catalog::parse_schema(&schema).unwrap())).unwrap();
+    let current = fs::read_to_string(root.join(CATALOG)).unwrap();
+    assert!(
+        current == generated,
+        "{} is out of date with {}; run `cargo run --example generate_signals`:\n{}",
+        CATALOG,
+        SCHEMA,
+        catalog::diff(&current, &generated)
+    );
+}
+
+#[test]
+fn parses_the_upstream_schema_layout() {
+    let schema = "
+extend type SignalCollection {
+  \"The last time any signal was seen matching the filter.\"
+  lastSeen: Time
+
+  \"\"\"Vehicle speed.
+  Unit: 'km/h'
+  VSS: Vehicle.Speed
+  Required Privileges: [VEHICLE_NON_LOCATION_DATA]\"\"\"
+  speed: SignalFloat @requiresAllOfPrivileges(privileges: [VEHICLE_NON_LOCATION_DATA])
+  # Comments are skipped.
+  undocumented: SignalFloat!
+}
+
+type Query {
+  signalsLatest(tokenId: Int!): SignalCollection
+}
+
+extend type SignalCollection @goModel(model: \"SignalCollection\") {
+  \"\"\"Current latitude of vehicle.
+  VSS: Vehicle.CurrentLocation.Latitude
+  \"\"\"
+  currentLocationLatitude(filter: SignalFilter): SignalFloat! @requiresAllOfPrivileges(privileges: [VEHICLE_ALL_TIME_LOCATION])
+}
+"
+    .replace('\n', "\r\n");
+
+    let err = catalog::parse_schema(&schema).err().unwrap();
+    assert_eq!(err, "signal undocumented has no VSS path");
+
+    let schema = schema.replace(
+        "  undocumented: SignalFloat!\r\n",
+        "  \"\"\"\r\n  VSS: Vehicle.Undocumented\r\n  \"\"\"\r\n  undocumented: SignalString!\r\n",
+    );
+    let signals = catalog::parse_schema(&schema).unwrap();
+    let names: Vec<&str> = signals.iter().map(|signal| signal.name.as_str()).collect();
+    assert_eq!(names, ["currentLocationLatitude", "speed", "undocumented"]);
+
+    let latitude = &signals[0];
+    assert!(latitude.location);
+    assert!(!latitude.string);
+
+    let speed = &signals[1];
+    assert_eq!(speed.description, "Vehicle speed.");
+    assert_eq!(speed.unit.as_deref(), Some("km/h"));
+    assert_eq!(speed.vss_path, "Vehicle.Speed");
+    assert!(!speed.location);
+
+    // A field without a description does not inherit the previous one.
+    let undocumented = &signals[2];
+    assert!(undocumented.string);
+    assert_eq!(undocumented.description, "");
+    assert_eq!(undocumented.unit, None);
+}